@@ -1,13 +1,22 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
 
 use crate::profiles::Profile;
-use crate::settings::registry::all_settings;
-use crate::settings::writer::write_setting;
+use crate::profiles::storage as profile_storage;
+use crate::settings::activation::{CommandRunner, activate};
+use crate::settings::apply::{ApplyOutcome, apply_changes};
+use crate::settings::backend::{PreferencesBackend, ReadError};
+use crate::settings::conflict::{Conflict, check_conflicts, same_reading};
+use crate::settings::plan::{Plan, plan_changes};
+use crate::settings::plist_value::PlistType;
+use crate::settings::reader::{available_setting_ids, prefetch, read_all, read_setting};
+use crate::settings::verify::verify_changes;
 use crate::settings::{
-    Constraint, FloatRange, IntRange, SettingDef, SettingValue, Tab,
+    Constraint, FloatRange, HostScope, IntRange, SettingChange, SettingDef, SettingValue, Tab,
+    same_value,
 };
-use crate::settings::reader::{read_all, available_setting_ids};
-use crate::profiles::storage as profile_storage;
+use crate::watcher::Refresh;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
@@ -15,6 +24,18 @@ pub enum View {
     Review,
     Profiles,
     ProfileNameInput,
+    Conflict,
+    DryRun,
+}
+
+/// How the user settled a change whose setting was modified elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Drop the pending change and keep the other program's value.
+    KeepTheirs,
+    Overwrite,
+    /// Leave the change pending without applying it.
+    Skip,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +48,8 @@ pub enum Message {
     AdjustLeft,
     AdjustRight,
     Toggle,
+    ResetSelected,
+    ResetGroup,
     OpenReview,
     ApplyChanges,
     CancelReview,
@@ -38,17 +61,42 @@ pub enum Message {
     TypeChar(char),
     Backspace,
     ConfirmInput,
+    Refreshed(Refresh),
+    Resolve(Resolution),
+    DryRun,
+    ToggleSnap,
 }
 
 pub struct App {
+    pub backend: Arc<dyn PreferencesBackend>,
+    pub runner: Box<dyn CommandRunner>,
     pub running: bool,
     pub view: View,
     pub tab: Tab,
     pub selected_row: usize,
+    /// Whether adjusting a slider jumps between the System Settings stops.
+    pub snap_to_stops: bool,
     pub settings_defs: Vec<SettingDef>,
     pub available_ids: HashSet<String>,
     pub live_values: HashMap<String, SettingValue>,
-    pub pending_changes: HashMap<String, SettingValue>,
+    /// Why settings missing from `live_values` couldn't be read.
+    pub read_errors: HashMap<String, ReadError>,
+    /// Settings read from a key stored with the wrong type.
+    pub drifted: HashMap<String, PlistType>,
+    pub pending_changes: HashMap<String, SettingChange>,
+    /// The live value each pending change started from.
+    pub pending_base: HashMap<String, Result<SettingValue, ReadError>>,
+    /// Host scopes from a loaded profile, used only when applying its changes.
+    pending_scopes: HashMap<String, HostScope>,
+    /// Settings another program changed since the TUI read them.
+    pub externally_changed: HashSet<String>,
+    last_write: Option<Instant>,
+    /// Conflicts still waiting for a resolution, first one shown.
+    pub conflicts: Vec<Conflict>,
+    /// Pending changes the user chose to skip in this apply.
+    held_back: HashSet<String>,
+    /// Why each setting from the last apply didn't read back as written.
+    pub unverified: HashMap<String, String>,
     pub profile_names: Vec<String>,
     pub profile_selected: usize,
     pub status_message: Option<String>,
@@ -57,21 +105,37 @@ pub struct App {
 }
 
 impl App {
-    pub fn new() -> Self {
-        let settings_defs = all_settings();
-        let available_ids = available_setting_ids(&settings_defs);
-        let live_values = read_all(&settings_defs);
+    pub fn new(
+        backend: Arc<dyn PreferencesBackend>,
+        runner: Box<dyn CommandRunner>,
+        settings_defs: Vec<SettingDef>,
+    ) -> Self {
+        prefetch(backend.as_ref(), &settings_defs);
+        let available_ids = available_setting_ids(backend.as_ref(), &settings_defs);
+        let readings = read_all(backend.as_ref(), &settings_defs);
         let profile_names = profile_storage::list().unwrap_or_default();
 
         Self {
+            backend,
+            runner,
             running: true,
             view: View::Settings,
             tab: Tab::Mouse,
             selected_row: 0,
+            snap_to_stops: false,
             settings_defs,
             available_ids,
-            live_values,
+            live_values: readings.values,
+            read_errors: readings.errors,
+            drifted: readings.drifted,
             pending_changes: HashMap::new(),
+            pending_base: HashMap::new(),
+            pending_scopes: HashMap::new(),
+            externally_changed: HashSet::new(),
+            last_write: None,
+            conflicts: Vec::new(),
+            held_back: HashSet::new(),
+            unverified: HashMap::new(),
             profile_names,
             profile_selected: 0,
             status_message: None,
@@ -84,7 +148,7 @@ impl App {
         let groups = self.tab.groups();
         self.settings_defs
             .iter()
-            .filter(|s| groups.contains(&s.group) && self.available_ids.contains(s.id))
+            .filter(|s| groups.contains(&s.group) && self.available_ids.contains(&*s.id))
             .collect()
     }
 
@@ -92,34 +156,149 @@ impl App {
         self.pending_changes.len()
     }
 
-    pub fn pending_diffs(&self) -> Vec<(&SettingDef, Option<&SettingValue>, &SettingValue)> {
+    pub fn pending_diffs(&self) -> Vec<(&SettingDef, Option<&SettingValue>, &SettingChange)> {
         let mut diffs = Vec::new();
-        for (id, new_val) in &self.pending_changes {
-            if let Some(def) = self.settings_defs.iter().find(|d| d.id == id) {
+        for (id, change) in &self.pending_changes {
+            if let Some(def) = self.settings_defs.iter().find(|d| d.id == *id) {
                 let old_val = self.live_values.get(id);
-                if old_val.is_some_and(|old| old == new_val) {
+                let unchanged = match change {
+                    SettingChange::Set(new_val) => old_val == Some(new_val),
+                    SettingChange::Reset => old_val.is_none(),
+                };
+                if unchanged {
                     continue;
                 }
-                diffs.push((def, old_val, new_val));
+                diffs.push((def, old_val, change));
             }
         }
-        diffs.sort_by_key(|(d, _, _)| d.id);
+        diffs.sort_by(|(a, _, _), (b, _, _)| a.id.cmp(&b.id));
         diffs
     }
 
+    /// The `defaults` commands applying the pending changes would run.
+    pub fn pending_plan(&self) -> anyhow::Result<Plan> {
+        let owned = self.scoped_changes();
+        let changes: Vec<_> = owned
+            .iter()
+            .map(|(def, change)| (def, change.clone()))
+            .collect();
+        plan_changes(&changes)
+    }
+
+    /// The pending changes sorted by id, each with its definition scoped to
+    /// the hosts a loaded profile asked for.
+    fn scoped_changes(&self) -> Vec<(SettingDef, SettingChange)> {
+        let mut changes: Vec<_> = self
+            .pending_changes
+            .iter()
+            .filter_map(|(id, change)| {
+                let mut def = self.settings_defs.iter().find(|d| d.id == *id)?.clone();
+                if let Some(scope) = self.pending_scopes.get(id) {
+                    def.host_scope = *scope;
+                }
+                Some((def, change.clone()))
+            })
+            .collect();
+        changes.sort_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+        changes
+    }
+
     pub fn any_requires_logout(&self) -> bool {
         self.pending_changes.keys().any(|id| {
             self.settings_defs
                 .iter()
-                .find(|d| d.id == id)
-                .is_some_and(|d| d.requires_logout)
+                .find(|d| d.id == *id)
+                .is_some_and(|d| d.requires_logout())
         })
     }
 
     pub fn effective_value(&self, id: &str) -> Option<&SettingValue> {
-        self.pending_changes
+        match self.pending_changes.get(id) {
+            Some(SettingChange::Set(value)) => Some(value),
+            Some(SettingChange::Reset) => None,
+            None => self.live_values.get(id),
+        }
+    }
+
+    pub fn read_error(&self, id: &str) -> Option<&ReadError> {
+        match self.pending_changes.get(id) {
+            Some(_) => None,
+            None => self.read_errors.get(id),
+        }
+    }
+
+    /// Whether the value a pending change was based on has since been
+    /// changed by another program.
+    pub fn is_stale(&self, id: &str) -> bool {
+        self.pending_base
             .get(id)
-            .or_else(|| self.live_values.get(id))
+            .is_some_and(|base| !same_reading(base, &self.live_reading(id)))
+    }
+
+    /// The live value, or why it couldn't be read.
+    fn live_reading(&self, id: &str) -> Result<SettingValue, ReadError> {
+        match self.live_values.get(id) {
+            Some(value) => Ok(value.clone()),
+            None => Err(self
+                .read_errors
+                .get(id)
+                .cloned()
+                .unwrap_or(ReadError::NotSet)),
+        }
+    }
+
+    fn set_pending(&mut self, id: String, change: SettingChange) {
+        if !self.pending_base.contains_key(&id) {
+            self.pending_base.insert(id.clone(), self.live_reading(&id));
+        }
+        self.externally_changed.remove(&id);
+        self.pending_changes.insert(id, change);
+    }
+
+    fn clear_pending(&mut self) {
+        self.pending_changes.clear();
+        self.pending_base.clear();
+        self.pending_scopes.clear();
+    }
+
+    /// Takes in a background re-read, noting which settings changed.
+    fn refresh(&mut self, refresh: Refresh) {
+        // A read that overlapped our own apply may have seen it half done.
+        if self.last_write.is_some_and(|t| refresh.started < t) {
+            return;
+        }
+        let readings = refresh.readings;
+        let changed: Vec<String> = self
+            .settings_defs
+            .iter()
+            .filter(|d| !same_value(self.live_values.get(&*d.id), readings.values.get(&*d.id)))
+            .map(|d| d.id.to_string())
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        self.live_values = readings.values;
+        self.read_errors = readings.errors;
+        self.drifted = readings.drifted;
+        self.status_message = Some(format!(
+            "{} settings changed outside myshenyatko",
+            changed.len()
+        ));
+        self.externally_changed.extend(changed);
+    }
+
+    fn set_live(&mut self, id: String, value: Result<SettingValue, ReadError>) {
+        self.drifted.remove(&id);
+        match value {
+            Ok(value) => {
+                self.read_errors.remove(&id);
+                self.live_values.insert(id, value);
+            }
+            Err(e) => {
+                self.live_values.remove(&id);
+                self.read_errors.insert(id, e);
+            }
+        }
     }
 
     pub fn update(&mut self, msg: Message) {
@@ -144,15 +323,11 @@ impl App {
                 }
             }
             Message::NavigateUp => match self.view {
-                View::Settings => {
-                    if self.selected_row > 0 {
-                        self.selected_row -= 1;
-                    }
+                View::Settings if self.selected_row > 0 => {
+                    self.selected_row -= 1;
                 }
-                View::Profiles => {
-                    if self.profile_selected > 0 {
-                        self.profile_selected -= 1;
-                    }
+                View::Profiles if self.profile_selected > 0 => {
+                    self.profile_selected -= 1;
                 }
                 _ => {}
             },
@@ -188,6 +363,16 @@ impl App {
                     _ => {}
                 }
             }
+            Message::ResetSelected => {
+                if self.view == View::Settings {
+                    self.reset_selected(false);
+                }
+            }
+            Message::ResetGroup => {
+                if self.view == View::Settings {
+                    self.reset_selected(true);
+                }
+            }
             Message::OpenReview => {
                 if !self.pending_changes.is_empty() {
                     self.view = View::Review;
@@ -195,13 +380,12 @@ impl App {
             }
             Message::ApplyChanges => {
                 if self.view == View::Review {
-                    self.apply_all_changes();
-                    self.view = View::Settings;
+                    self.begin_apply();
                 }
             }
             Message::CancelReview => {
                 if self.view == View::Review {
-                    self.pending_changes.clear();
+                    self.clear_pending();
                     self.view = View::Settings;
                     self.status_message = Some("Changes discarded".to_string());
                 }
@@ -231,7 +415,15 @@ impl App {
                 }
             }
             Message::Back => {
-                self.view = View::Settings;
+                if self.view == View::Conflict {
+                    self.conflicts.clear();
+                    self.held_back.clear();
+                    self.view = View::Review;
+                } else if self.view == View::DryRun {
+                    self.view = View::Review;
+                } else {
+                    self.view = View::Settings;
+                }
             }
             Message::TypeChar(c) => {
                 if self.view == View::ProfileNameInput {
@@ -252,6 +444,27 @@ impl App {
                     }
                 }
             }
+            Message::Refreshed(refresh) => self.refresh(refresh),
+            Message::DryRun => {
+                if self.view == View::Review {
+                    self.view = View::DryRun;
+                }
+            }
+            Message::ToggleSnap => {
+                if self.view == View::Settings {
+                    self.snap_to_stops = !self.snap_to_stops;
+                    self.status_message = Some(if self.snap_to_stops {
+                        "Sliders snap to System Settings stops".to_string()
+                    } else {
+                        "Sliders move in fine steps".to_string()
+                    });
+                }
+            }
+            Message::Resolve(resolution) => {
+                if self.view == View::Conflict {
+                    self.resolve_conflict(resolution);
+                }
+            }
         }
     }
 
@@ -266,16 +479,33 @@ impl App {
             .cloned()
             .unwrap_or_else(|| def.default_value());
 
-        let new_value = match (&current, &def.constraint) {
-            (SettingValue::Float(v), Constraint::FloatRange(FloatRange { min, max, step })) => {
+        let snap = def
+            .scale
+            .as_ref()
+            .filter(|scale| self.snap_to_stops && !scale.stops.is_empty());
+        let new_value = match (&current, &def.constraint, snap) {
+            (SettingValue::Float(v), _, Some(scale)) => {
+                scale.next_stop(*v, direction).map(SettingValue::Float)
+            }
+            (SettingValue::Int(v), _, Some(scale)) => scale
+                .next_stop(*v as f64, direction)
+                .map(|stop| SettingValue::Int(stop.round() as i64)),
+            (SettingValue::Float(v), Constraint::FloatRange(FloatRange { min, max, step }), _) => {
                 let new = (v + direction as f64 * step).clamp(*min, *max);
                 Some(SettingValue::Float((new * 100.0).round() / 100.0))
             }
-            (SettingValue::Int(v), Constraint::IntRange(IntRange { min, max })) => {
+            (SettingValue::Int(v), Constraint::IntRange(IntRange { min, max }), _) => {
                 let new = (*v + direction as i64).clamp(*min, *max);
                 Some(SettingValue::Int(new))
             }
-            (SettingValue::Str(v), Constraint::StringOptions(opts)) => {
+            (value, Constraint::Enum(options), _) => {
+                let idx = options.iter().position(|o| o.value == *value);
+                let new_idx = idx.map_or(0, |idx| {
+                    (idx as i32 + direction).rem_euclid(options.len() as i32) as usize
+                });
+                options.get(new_idx).map(|o| o.value.clone())
+            }
+            (SettingValue::Str(v), Constraint::StringOptions(opts), _) => {
                 if let Some(idx) = opts.iter().position(|o| o == v) {
                     let new_idx = (idx as i32 + direction).rem_euclid(opts.len() as i32) as usize;
                     Some(SettingValue::Str(opts[new_idx].to_string()))
@@ -287,7 +517,7 @@ impl App {
         };
 
         if let Some(val) = new_value {
-            self.pending_changes.insert(id, val);
+            self.set_pending(id, SettingChange::Set(val));
         }
     }
 
@@ -303,57 +533,150 @@ impl App {
             .unwrap_or_else(|| def.default_value());
 
         match current {
+            _ if matches!(def.constraint, Constraint::Enum(_)) => self.adjust_selected(1),
             SettingValue::Bool(v) => {
-                self.pending_changes.insert(id, SettingValue::Bool(!v));
+                self.set_pending(id, SettingChange::Set(SettingValue::Bool(!v)));
             }
             SettingValue::Str(ref v) => {
-                if let Constraint::StringOptions(ref opts) = def.constraint {
-                    if let Some(idx) = opts.iter().position(|o| o == v) {
-                        let next = (idx + 1) % opts.len();
-                        self.pending_changes
-                            .insert(id, SettingValue::Str(opts[next].to_string()));
-                    }
+                if let Constraint::StringOptions(ref opts) = def.constraint
+                    && let Some(idx) = opts.iter().position(|o| o == v)
+                {
+                    let next = (idx + 1) % opts.len();
+                    self.set_pending(
+                        id,
+                        SettingChange::Set(SettingValue::Str(opts[next].to_string())),
+                    );
                 }
             }
             _ => {}
         }
     }
 
-    fn apply_all_changes(&mut self) {
-        let mut applied = 0;
-        let mut errors = Vec::new();
-        let defs: Vec<_> = self.settings_defs.clone();
-        for (id, value) in &self.pending_changes {
-            if let Some(def) = defs.iter().find(|d| d.id == id) {
-                match write_setting(def, value) {
-                    Ok(()) => {
-                        self.live_values.insert(id.clone(), value.clone());
-                        applied += 1;
-                    }
-                    Err(e) => errors.push(format!("{}: {e}", def.description)),
-                }
+    /// Marks the selected setting, or every visible setting in its group,
+    /// to be reset to the system default.
+    fn reset_selected(&mut self, whole_group: bool) {
+        let visible = self.visible_settings();
+        let Some(def) = visible.get(self.selected_row) else {
+            return;
+        };
+        let ids: Vec<String> = if whole_group {
+            visible
+                .iter()
+                .filter(|d| d.group == def.group)
+                .map(|d| d.id.to_string())
+                .collect()
+        } else {
+            vec![def.id.to_string()]
+        };
+        for id in ids {
+            self.set_pending(id, SettingChange::Reset);
+        }
+    }
+
+    /// Re-reads every setting about to be written. If any changed since its
+    /// pending change was made, asks about each one before applying.
+    fn begin_apply(&mut self) {
+        let owned = self.scoped_changes();
+        let bases: Vec<_> = owned
+            .iter()
+            .filter_map(|(def, _)| Some((def, self.pending_base.get(&*def.id)?.clone())))
+            .collect();
+        let mut conflicts = check_conflicts(self.backend.as_ref(), &bases);
+        conflicts.sort_by(|a, b| a.id.cmp(&b.id));
+        self.conflicts = conflicts;
+        self.held_back.clear();
+        self.continue_apply();
+    }
+
+    fn resolve_conflict(&mut self, resolution: Resolution) {
+        if self.conflicts.is_empty() {
+            return;
+        }
+        let conflict = self.conflicts.remove(0);
+        match resolution {
+            Resolution::KeepTheirs => {
+                self.pending_changes.remove(&conflict.id);
+                self.pending_base.remove(&conflict.id);
+                self.pending_scopes.remove(&conflict.id);
+                self.set_live(conflict.id, conflict.current);
+            }
+            Resolution::Overwrite => {}
+            Resolution::Skip => {
+                self.held_back.insert(conflict.id);
             }
         }
-        self.pending_changes.clear();
+        self.continue_apply();
+    }
+
+    fn continue_apply(&mut self) {
+        if !self.conflicts.is_empty() {
+            self.view = View::Conflict;
+            return;
+        }
+        self.apply_all_changes();
+        self.held_back.clear();
+        self.view = View::Settings;
+    }
+
+    fn apply_all_changes(&mut self) {
+        // Failures from an earlier apply don't describe this one.
+        self.unverified.clear();
+        let owned = self.scoped_changes();
+        let changes: Vec<_> = owned
+            .iter()
+            .filter(|(def, _)| !self.held_back.contains(&*def.id))
+            .map(|(def, change)| (def, change.clone()))
+            .collect();
 
-        self.status_message = if errors.is_empty() {
-            Some(format!("{applied} settings applied"))
+        let report = apply_changes(self.backend.as_ref(), &changes);
+        self.last_write = Some(Instant::now());
+        if report.outcome() != ApplyOutcome::Succeeded {
+            self.status_message = Some(report.to_string());
+            return;
+        }
+        let failures = verify_changes(self.backend.as_ref(), &changes);
+        let defs: Vec<_> = changes.iter().map(|(def, _)| *def).collect();
+        let activation = activate(self.runner.as_ref(), &defs);
+        self.status_message = Some(if failures.is_empty() {
+            format!("{report}; {activation}")
         } else {
-            Some(format!(
-                "{applied} applied, {} failed: {}",
-                errors.len(),
-                errors.join(", ")
-            ))
-        };
+            let names: Vec<_> = failures.iter().map(|f| f.description.as_str()).collect();
+            format!("{report}; {activation}; didn't stick: {}", names.join(", "))
+        });
+
+        // Show what's actually stored for settings that didn't stick.
+        let reread: Vec<_> = failures
+            .iter()
+            .filter_map(|f| defs.iter().find(|d| d.id == f.id))
+            .map(|def| (def.id.to_string(), read_setting(self.backend.as_ref(), def)))
+            .collect();
+        self.unverified = failures
+            .into_iter()
+            .map(|f| (f.id.clone(), f.problems.join("; ")))
+            .collect();
+        let applied: Vec<_> = changes
+            .iter()
+            .map(|(def, change)| (def.id.to_string(), change.clone()))
+            .collect();
+        for (id, change) in applied {
+            self.pending_changes.remove(&id);
+            self.pending_base.remove(&id);
+            self.pending_scopes.remove(&id);
+            match change {
+                SettingChange::Set(value) => self.set_live(id, Ok(value)),
+                SettingChange::Reset => self.set_live(id, Err(ReadError::NotSet)),
+            }
+        }
+        for (id, value) in reread {
+            self.set_live(id, value);
+        }
     }
 
     fn apply_selected_profile(&mut self) {
-        if let Some(name) = self.profile_names.get(self.profile_selected) {
-            match profile_storage::load(name) {
+        if let Some(name) = self.profile_names.get(self.profile_selected).cloned() {
+            match profile_storage::load(&name) {
                 Ok(profile) => {
-                    for (id, value) in profile.settings {
-                        self.pending_changes.insert(id, value);
-                    }
+                    self.load_profile(profile);
                     self.status_message =
                         Some(format!("Loaded profile '{}' as pending changes", name));
                     self.view = View::Review;
@@ -365,6 +688,17 @@ impl App {
         }
     }
 
+    /// Makes a profile's settings pending changes, written to its host scopes.
+    fn load_profile(&mut self, profile: Profile) {
+        for (id, value) in profile.settings {
+            match profile.host_scopes.get(&id) {
+                Some(scope) => self.pending_scopes.insert(id.clone(), *scope),
+                None => self.pending_scopes.remove(&id),
+            };
+            self.set_pending(id, SettingChange::Set(value));
+        }
+    }
+
     fn delete_selected_profile(&mut self) {
         if let Some(name) = self.profile_names.get(self.profile_selected).cloned() {
             match profile_storage::delete(&name) {
@@ -384,10 +718,14 @@ impl App {
 
     fn save_current_as_profile(&mut self) {
         let mut all_settings = self.live_values.clone();
-        for (id, value) in &self.pending_changes {
-            all_settings.insert(id.clone(), value.clone());
+        for (id, change) in &self.pending_changes {
+            match change {
+                SettingChange::Set(value) => all_settings.insert(id.clone(), value.clone()),
+                SettingChange::Reset => all_settings.remove(id),
+            };
         }
-        let profile = Profile::new(self.input_buffer.clone(), all_settings);
+        let mut profile = Profile::new(self.input_buffer.clone(), all_settings);
+        profile.record_host_scopes(&self.settings_defs);
         match profile_storage::save(&profile) {
             Ok(()) => {
                 self.status_message =
@@ -400,3 +738,39 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::activation::NoopRunner;
+    use crate::settings::backend::MemoryBackend;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn plans_a_loaded_profile_with_its_host_scopes() {
+        let mut app = App::new(
+            Arc::new(MemoryBackend::default()),
+            Box::new(NoopRunner),
+            all_settings(),
+        );
+        let mut profile = Profile::new(
+            "Laptop".into(),
+            HashMap::from([("key_repeat".to_string(), SettingValue::Int(2))]),
+        );
+        profile
+            .host_scopes
+            .insert("key_repeat".into(), HostScope::CurrentHost);
+        app.load_profile(profile);
+
+        let plan = app.pending_plan().unwrap();
+        assert_eq!(plan.operations.len(), 1);
+        assert!(plan.operations[0].current_host);
+        assert!(plan.operations[0].command.contains("-currentHost"));
+        let def = app
+            .settings_defs
+            .iter()
+            .find(|d| d.id == "key_repeat")
+            .unwrap();
+        assert_eq!(def.host_scope, HostScope::AnyHost);
+    }
+}
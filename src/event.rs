@@ -26,12 +26,16 @@ pub fn map_key(key: KeyEvent) -> Option<Message> {
         KeyCode::Left | KeyCode::Char('h') => Some(Message::AdjustLeft),
         KeyCode::Right | KeyCode::Char('l') => Some(Message::AdjustRight),
         KeyCode::Char(' ') | KeyCode::Enter => Some(Message::Toggle),
+        KeyCode::Char('x') => Some(Message::ResetSelected),
+        KeyCode::Char('X') => Some(Message::ResetGroup),
         KeyCode::Char('r') => Some(Message::OpenReview),
         KeyCode::Char('a') => Some(Message::ApplyChanges),
         KeyCode::Char('c') => Some(Message::CancelReview),
         KeyCode::Char('s') => Some(Message::SaveProfile),
         KeyCode::Char('p') => Some(Message::OpenProfiles),
         KeyCode::Char('d') => Some(Message::DeleteProfile),
+        KeyCode::Char('D') => Some(Message::DryRun),
+        KeyCode::Char('S') => Some(Message::ToggleSnap),
         KeyCode::Char('n') => Some(Message::CreateProfile),
         KeyCode::Esc => Some(Message::Back),
         _ => None,
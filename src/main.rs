@@ -3,27 +3,64 @@ mod event;
 mod profiles;
 mod settings;
 mod ui;
+mod watcher;
 
-use std::io;
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use crossterm::event::{Event, KeyCode, KeyEvent};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::execute;
-use ratatui::backend::CrosstermBackend;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
 use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use serde::Serialize;
 
-use app::{App, Message, View};
+use app::{App, Message, Resolution, View};
+use profiles::ExportFormat;
+use settings::activation::{ActivationReport, CommandRunner, NoopRunner, SystemRunner};
+use settings::apply::{ApplyOutcome, ApplyReport};
+use settings::backend::{DefaultsCliBackend, MemoryBackend, PreferencesBackend, ReadError};
+use settings::conflict::ConflictPolicy;
+use settings::lint::{Diagnostic, Severity};
+use settings::plist_backend::PlistBackend;
+use settings::plist_value::PlistType;
+use settings::{Host, HostScope, SettingChange, SettingDef, SettingGroup, SettingValue};
 
 #[derive(Parser)]
-#[command(name = "myshenyatko", about = "macOS mouse/trackpad/cursor/keyboard settings TUI")]
+#[command(
+    name = "myshenyatko",
+    about = "macOS mouse/trackpad/cursor/keyboard settings TUI"
+)]
 struct Cli {
+    /// Read and write preferences in a JSON file instead of macOS defaults
+    #[arg(long, global = true, value_name = "PATH")]
+    prefs_file: Option<PathBuf>,
+
+    /// How to read macOS preferences
+    #[arg(long, global = true, value_enum, default_value_t = ReaderMode::Native)]
+    reader: ReaderMode,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReaderMode {
+    /// Parse the domain plists directly, falling back to `defaults`
+    Native,
+    /// Fetch each domain once with `defaults export`, in parallel
+    Export,
+    /// Spawn `defaults read` for every key
+    Cli,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Manage saved profiles
@@ -32,7 +69,43 @@ enum Commands {
         action: ProfileAction,
     },
     /// Dump all current settings as JSON
-    Dump,
+    Dump {
+        /// Print how long each read phase took to stderr
+        #[arg(long)]
+        timings: bool,
+        /// Only include settings that differ from the macOS factory default
+        #[arg(long)]
+        changed_only: bool,
+    },
+    /// Check for keys stored with the wrong type
+    Doctor {
+        /// Rewrite drifted keys with the type their setting expects
+        #[arg(long)]
+        fix_types: bool,
+    },
+    /// Reset settings to the system default by deleting their keys
+    #[command(group(ArgGroup::new("target").required(true).args(["id", "group", "all"])))]
+    Reset {
+        /// Setting id to reset
+        id: Option<String>,
+        /// Reset every setting in a group
+        #[arg(long, value_enum)]
+        group: Option<SettingGroup>,
+        /// Reset every known setting
+        #[arg(long)]
+        all: bool,
+    },
+    /// Work with the settings registry
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum RegistryAction {
+    /// Check the built-in settings and registry files for mistakes
+    Lint,
 }
 
 #[derive(Subcommand)]
@@ -40,47 +113,152 @@ enum ProfileAction {
     /// List saved profiles
     List,
     /// Apply a saved profile
-    Apply { name: String },
-    /// Export a profile as JSON to stdout
-    Export { name: String },
-    /// Import a profile from a JSON file
-    Import { file: String },
+    Apply {
+        name: String,
+        /// What to do with settings changed by another program while applying
+        #[arg(long, value_enum, default_value_t = ConflictPolicy::Fail)]
+        on_conflict: ConflictPolicy,
+        /// Same as --on-conflict=overwrite
+        #[arg(long, conflicts_with = "on_conflict")]
+        force: bool,
+        /// Print the `defaults` commands that would run, and change nothing
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the dry run as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+    /// Export a profile to stdout, as JSON or another format
+    Export {
+        name: String,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Sign a mobileconfig with this keychain certificate
+        #[arg(long, value_name = "IDENTITY")]
+        sign: Option<String>,
+        /// Reverse-DNS prefix for mobileconfig payload identifiers
+        #[arg(long, value_name = "PREFIX")]
+        identifier: Option<String>,
+    },
+    /// Import a profile from JSON, a .mobileconfig, or an XML or binary plist
+    Import {
+        file: String,
+        /// Profile name; defaults to the name in a JSON profile, or the file name
+        #[arg(long)]
+        name: Option<String>,
+        /// Domain of a plain plist; defaults to its file name
+        #[arg(long)]
+        domain: Option<String>,
+    },
+    /// Create a profile from the `defaults write` lines in a shell script
+    ImportScript {
+        file: String,
+        /// Profile name; defaults to the script's file name
+        #[arg(long)]
+        name: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let runner = make_runner(cli.prefs_file.is_some());
+    let backend = make_backend(cli.prefs_file, cli.reader)?;
 
     match cli.command {
-        None => run_tui(),
-        Some(Commands::Dump) => cmd_dump(),
+        None => run_tui(backend, runner),
+        Some(Commands::Dump {
+            timings,
+            changed_only,
+        }) => cmd_dump(backend.as_ref(), timings, changed_only),
+        Some(Commands::Doctor { fix_types }) => cmd_doctor(backend.as_ref(), fix_types),
+        Some(Commands::Reset { id, group, all }) => {
+            cmd_reset(backend.as_ref(), runner.as_ref(), id.as_deref(), group, all)
+        }
+        Some(Commands::Registry {
+            action: RegistryAction::Lint,
+        }) => cmd_registry_lint(),
         Some(Commands::Profile { action }) => match action {
             ProfileAction::List => cmd_profile_list(),
-            ProfileAction::Apply { name } => cmd_profile_apply(&name),
-            ProfileAction::Export { name } => cmd_profile_export(&name),
-            ProfileAction::Import { file } => cmd_profile_import(&file),
+            ProfileAction::Apply {
+                name,
+                dry_run: true,
+                json,
+                ..
+            } => cmd_profile_plan(&name, json),
+            ProfileAction::Apply {
+                name,
+                on_conflict,
+                force,
+                ..
+            } => {
+                let policy = if force {
+                    ConflictPolicy::Overwrite
+                } else {
+                    on_conflict
+                };
+                cmd_profile_apply(backend.as_ref(), runner.as_ref(), &name, policy)
+            }
+            ProfileAction::Export {
+                name,
+                format,
+                sign,
+                identifier,
+            } => cmd_profile_export(&name, format, sign.as_deref(), identifier.as_deref()),
+            ProfileAction::Import { file, name, domain } => {
+                cmd_profile_import(&file, name, domain.as_deref())
+            }
+            ProfileAction::ImportScript { file, name } => cmd_profile_import_script(&file, name),
         },
     }
 }
 
-fn run_tui() -> Result<()> {
+fn make_backend(
+    prefs_file: Option<PathBuf>,
+    reader: ReaderMode,
+) -> Result<Box<dyn PreferencesBackend>> {
+    Ok(match (prefs_file, reader) {
+        (Some(path), _) => Box::new(MemoryBackend::open(path)?),
+        (None, ReaderMode::Native) => Box::new(PlistBackend::for_current_user()?),
+        (None, ReaderMode::Export) => Box::new(PlistBackend::exported()),
+        (None, ReaderMode::Cli) => Box::new(DefaultsCliBackend),
+    })
+}
+
+/// Activation commands only make sense against the live macOS preferences.
+fn make_runner(prefs_file: bool) -> Box<dyn CommandRunner> {
+    if prefs_file {
+        Box::new(NoopRunner)
+    } else {
+        Box::new(SystemRunner)
+    }
+}
+
+fn run_tui(prefs: Box<dyn PreferencesBackend>, runner: Box<dyn CommandRunner>) -> Result<()> {
+    let defs = settings::registry::load()?;
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
+    let prefs: Arc<dyn PreferencesBackend> = prefs.into();
+    let mut app = App::new(prefs.clone(), runner, defs);
+    let refreshes = watcher::spawn(prefs, app.settings_defs.clone(), Duration::from_secs(3));
 
     while app.running {
         terminal.draw(|frame| ui::draw(frame, &app))?;
 
-        if let Some(evt) = event::poll_event(Duration::from_millis(100))? {
-            if let Event::Key(key) = evt {
-                if app.view == View::ProfileNameInput {
-                    handle_input_key(&mut app, key);
-                } else if let Some(msg) = event::map_key(key) {
-                    app.update(msg);
-                }
+        while let Ok(refresh) = refreshes.try_recv() {
+            app.update(Message::Refreshed(refresh));
+        }
+
+        if let Some(Event::Key(key)) = event::poll_event(Duration::from_millis(100))? {
+            if app.view == View::ProfileNameInput {
+                handle_input_key(&mut app, key);
+            } else if app.view == View::Conflict {
+                handle_conflict_key(&mut app, key);
+            } else if let Some(msg) = event::map_key(key) {
+                app.update(msg);
             }
         }
     }
@@ -100,14 +278,92 @@ fn handle_input_key(app: &mut App, key: KeyEvent) {
     }
 }
 
-fn cmd_dump() -> Result<()> {
-    let defs = settings::registry::all_settings();
-    let values = settings::reader::read_all(&defs);
-    let json = serde_json::to_string_pretty(&values)?;
+fn handle_conflict_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('t') => app.update(Message::Resolve(Resolution::KeepTheirs)),
+        KeyCode::Char('o') => app.update(Message::Resolve(Resolution::Overwrite)),
+        KeyCode::Char('s') => app.update(Message::Resolve(Resolution::Skip)),
+        KeyCode::Esc => app.update(Message::Back),
+        _ => {}
+    }
+}
+
+fn cmd_dump(backend: &dyn PreferencesBackend, timings: bool, changed_only: bool) -> Result<()> {
+    let defs = settings::registry::load()?;
+
+    let start = Instant::now();
+    let fetches = settings::reader::prefetch(backend, &defs);
+    let prefetched = start.elapsed();
+    let readings = settings::reader::read_all(backend, &defs);
+    let total = start.elapsed();
+
+    // Unset keys are at their factory default; unreadable ones are always shown.
+    let entries: BTreeMap<&str, DumpEntry> = defs
+        .iter()
+        .filter_map(|def| {
+            let value = readings.values.get(&*def.id);
+            let error = readings.errors.get(&*def.id);
+            let unchanged = match (value, error) {
+                (Some(value), _) => def.is_factory(value),
+                (None, error) => error.is_none_or(ReadError::is_not_set),
+            };
+            if changed_only && unchanged {
+                return None;
+            }
+            Some((
+                &*def.id,
+                DumpEntry {
+                    value,
+                    label: value.and_then(|value| def.label(value)),
+                    host: def.host_scope,
+                    stored_as: readings.drifted.get(&*def.id).copied(),
+                    status: error.map(ReadError::kind),
+                    error: error.filter(|e| !e.is_not_set()).map(ToString::to_string),
+                },
+            ))
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&entries)?;
     println!("{json}");
+
+    if timings {
+        eprintln!("Timings:");
+        for fetch in &fetches {
+            let host = if fetch.host == Host::Current {
+                " (currentHost)"
+            } else {
+                ""
+            };
+            let status = if fetch.loaded { "" } else { " (fallback)" };
+            let domain = format!("{}{host}", fetch.domain);
+            eprintln!("  {domain:<52} {:>8.1?}{status}", fetch.elapsed);
+        }
+        eprintln!("  {:<52} {:>8.1?}", "prefetch", prefetched);
+        eprintln!("  {:<52} {:>8.1?}", "read keys", total - prefetched);
+        eprintln!("  {:<52} {:>8.1?}", "total", total);
+    }
     Ok(())
 }
 
+#[derive(Serialize)]
+struct DumpEntry<'a> {
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    value: Option<&'a SettingValue>,
+    /// The name of the value, for settings with named values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    #[serde(skip_serializing_if = "HostScope::is_any_host")]
+    host: HostScope,
+    /// Set when the value was coerced from a key stored with another type.
+    #[serde(rename = "storedAs", skip_serializing_if = "Option::is_none")]
+    stored_as: Option<PlistType>,
+    /// Set when there's no value: why it couldn't be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 fn cmd_profile_list() -> Result<()> {
     let names = profiles::storage::list()?;
     if names.is_empty() {
@@ -120,42 +376,295 @@ fn cmd_profile_list() -> Result<()> {
     Ok(())
 }
 
-fn cmd_profile_apply(name: &str) -> Result<()> {
-    let profile = profiles::storage::load(name)
-        .context(format!("loading profile '{name}'"))?;
-    let defs = settings::registry::all_settings();
-    let mut applied = 0;
-    let mut errors = Vec::new();
+fn cmd_profile_apply(
+    backend: &dyn PreferencesBackend,
+    runner: &dyn CommandRunner,
+    name: &str,
+    policy: ConflictPolicy,
+) -> Result<()> {
+    let (profile, defs) = profile_defs(name)?;
+    let mut changes = profile.changes(&defs);
+
+    let report = settings::apply::apply_guarded(backend, &changes, policy);
+    for conflict in &report.conflicts {
+        eprintln!("Changed outside: {conflict}");
+    }
+    if policy == ConflictPolicy::Fail && !report.conflicts.is_empty() {
+        eprintln!("Use --on-conflict or --force to apply over changes made meanwhile.");
+    }
+    print_apply_report(&report)?;
+    changes.retain(|(def, _)| report.applied.iter().any(|id| *id == def.id));
+    println!(
+        "Applied {} settings from profile '{name}'.",
+        report.applied.len()
+    );
+    finish_apply(backend, runner, &changes)
+}
+
+/// Loads a profile along with the registry, scoped to the profile's hosts.
+fn profile_defs(name: &str) -> Result<(profiles::Profile, Vec<SettingDef>)> {
+    let profile = profiles::storage::load(name).context(format!("loading profile '{name}'"))?;
+    let mut defs = settings::registry::load()?;
+    profile.apply_host_scopes(&mut defs);
+    Ok((profile, defs))
+}
 
-    for (id, value) in &profile.settings {
-        if let Some(def) = defs.iter().find(|d| d.id == id) {
-            match settings::writer::write_setting(def, value) {
-                Ok(()) => applied += 1,
-                Err(e) => errors.push(format!("{}: {e}", def.description)),
+fn cmd_profile_plan(name: &str, json: bool) -> Result<()> {
+    let (profile, defs) = profile_defs(name)?;
+    let plan = settings::plan::plan_changes(&profile.changes(&defs))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    } else {
+        print!("{plan}");
+    }
+    Ok(())
+}
+
+fn cmd_doctor(backend: &dyn PreferencesBackend, fix_types: bool) -> Result<()> {
+    let defs = settings::registry::load()?;
+    settings::reader::prefetch(backend, &defs);
+    let drift = settings::drift::find_drift(backend, &defs);
+    if drift.is_empty() {
+        println!("All stored types match.");
+        return Ok(());
+    }
+
+    let mut unfixed = 0;
+    for d in &drift {
+        if !fix_types {
+            let reads_as = match &d.coerced {
+                Ok(value) => format!("reads as {value}"),
+                Err(e) => format!("unreadable: {e}"),
+            };
+            println!("{}: {d} ({reads_as})", d.id);
+            unfixed += 1;
+            continue;
+        }
+        match settings::drift::fix_drift(backend, d) {
+            Ok(value) => println!("Fixed {}: {d}; rewrote as {value}", d.id),
+            Err(e) => {
+                eprintln!("Couldn't fix {}: {d}: {e}", d.id);
+                unfixed += 1;
             }
         }
     }
+    if unfixed == 0 {
+        return Ok(());
+    }
+    if fix_types {
+        anyhow::bail!("{unfixed} keys could not be fixed")
+    }
+    anyhow::bail!("{unfixed} keys have drifted types; run with --fix-types to rewrite them")
+}
 
-    println!("Applied {applied} settings from profile '{name}'.");
-    if !errors.is_empty() {
-        eprintln!("Errors:");
-        for e in errors {
+fn cmd_registry_lint() -> Result<()> {
+    let dir =
+        settings::registry_file::registry_dir().context("could not determine home directory")?;
+    let read = settings::registry_file::read_dir(&dir)?;
+    let mut diagnostics: Vec<_> = read.bad.iter().map(Diagnostic::unreadable).collect();
+    diagnostics.extend(settings::lint::lint(
+        &settings::registry::all_settings(),
+        &read.files,
+    ));
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        anyhow::bail!("{errors} errors in the registry");
+    }
+    println!(
+        "Checked the built-in settings and {} registry files in {}.",
+        read.files.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+fn cmd_reset(
+    backend: &dyn PreferencesBackend,
+    runner: &dyn CommandRunner,
+    id: Option<&str>,
+    group: Option<SettingGroup>,
+    all: bool,
+) -> Result<()> {
+    let defs = settings::registry::load()?;
+    let changes: Vec<_> = defs
+        .iter()
+        .filter(|d| all || id == Some(&*d.id) || Some(d.group) == group)
+        .map(|def| (def, SettingChange::Reset))
+        .collect();
+    if let Some(id) = id
+        && changes.is_empty()
+    {
+        anyhow::bail!("unknown setting '{id}'");
+    }
+
+    let report = settings::apply::apply_changes(backend, &changes);
+    print_apply_report(&report)?;
+    println!(
+        "Reset {} settings to system defaults.",
+        report.applied.len()
+    );
+    finish_apply(backend, runner, &changes)
+}
+
+/// Prints what went wrong with a failed apply and turns it into an error.
+fn print_apply_report(report: &ApplyReport) -> Result<()> {
+    let outcome = report.outcome();
+    if outcome == ApplyOutcome::Succeeded {
+        return Ok(());
+    }
+    if let Some(failure) = &report.failure {
+        eprintln!(
+            "Failed to apply {} ({}): {}",
+            failure.description, failure.id, failure.error
+        );
+    }
+    if outcome == ApplyOutcome::RolledBack {
+        eprintln!(
+            "Rolled back {} settings; nothing was changed.",
+            report.applied.len()
+        );
+    } else {
+        eprintln!("Rollback failed, these keys may be left modified:");
+        for e in &report.rollback_errors {
             eprintln!("  {e}");
         }
     }
-    Ok(())
+    anyhow::bail!("changes were not applied")
 }
 
-fn cmd_profile_export(name: &str) -> Result<()> {
-    let json = profiles::storage::export_json(name)?;
-    println!("{json}");
+/// Checks that applied changes read back as written, then activates them.
+/// Fails if any setting didn't stick.
+fn finish_apply(
+    backend: &dyn PreferencesBackend,
+    runner: &dyn CommandRunner,
+    changes: &[(&SettingDef, SettingChange)],
+) -> Result<()> {
+    let failures = settings::verify::verify_changes(backend, changes);
+    let defs: Vec<_> = changes.iter().map(|(def, _)| *def).collect();
+    print_activation_report(&settings::activation::activate(runner, &defs));
+    if failures.is_empty() {
+        return Ok(());
+    }
+    eprintln!("These settings didn't read back as written:");
+    for failure in &failures {
+        eprintln!("  {} ({})", failure, failure.id);
+    }
+    anyhow::bail!("{} settings did not take effect", failures.len())
+}
+
+fn print_activation_report(report: &ActivationReport) {
+    for e in &report.errors {
+        eprintln!("Activation failed: {e}");
+    }
+    if !report.needs_logout.is_empty() {
+        println!(
+            "Log out and back in for: {}",
+            report.needs_logout.join(", ")
+        );
+    }
+}
+
+fn cmd_profile_export(
+    name: &str,
+    format: ExportFormat,
+    sign: Option<&str>,
+    identifier: Option<&str>,
+) -> Result<()> {
+    if sign.is_some() && format != ExportFormat::Mobileconfig {
+        anyhow::bail!("--sign only applies to --format mobileconfig");
+    }
+    if identifier.is_some() && format != ExportFormat::Mobileconfig {
+        anyhow::bail!("--identifier only applies to --format mobileconfig");
+    }
+    match format {
+        ExportFormat::Json => {
+            println!(
+                "{}",
+                profiles::storage::export_json(name, &settings::registry::load()?)?
+            );
+        }
+        ExportFormat::Sh => {
+            let (profile, defs) = profile_defs(name)?;
+            print!("{}", profiles::shell::to_shell(&profile, &defs)?);
+        }
+        ExportFormat::Mobileconfig => {
+            let (profile, defs) = profile_defs(name)?;
+            let identifier = identifier.unwrap_or(profiles::mobileconfig::IDENTIFIER);
+            let mut config = profiles::mobileconfig::to_mobileconfig(&profile, &defs, identifier)?;
+            if let Some(identity) = sign {
+                config = profiles::mobileconfig::sign(&config, identity)?;
+            }
+            io::stdout().write_all(&config)?;
+        }
+        ExportFormat::Nix => {
+            let (profile, defs) = profile_defs(name)?;
+            print!("{}", profiles::nix::to_nix_darwin(&profile, &defs)?);
+        }
+        ExportFormat::HomeManager => {
+            let (profile, defs) = profile_defs(name)?;
+            print!("{}", profiles::nix::to_home_manager(&profile, &defs)?);
+        }
+        ExportFormat::Ansible => {
+            let (profile, defs) = profile_defs(name)?;
+            print!("{}", profiles::ansible::to_ansible(&profile, &defs)?);
+        }
+    }
     Ok(())
 }
 
-fn cmd_profile_import(file: &str) -> Result<()> {
-    let json = std::fs::read_to_string(file)
-        .context(format!("reading file '{file}'"))?;
-    let profile = profiles::storage::import_json(&json)?;
-    println!("Imported profile '{}'.", profile.name);
+fn cmd_profile_import(file: &str, name: Option<String>, domain: Option<&str>) -> Result<()> {
+    let bytes = std::fs::read(file).context(format!("reading file '{file}'"))?;
+    // Saved profiles are JSON; anything else is a plist or configuration profile.
+    if bytes.trim_ascii_start().starts_with(b"{") {
+        if domain.is_some() {
+            anyhow::bail!("--domain only applies to plain plists");
+        }
+        let profile = profiles::storage::import_json(&String::from_utf8(bytes)?, name)?;
+        println!("Imported profile '{}'.", profile.name);
+        return Ok(());
+    }
+    let defs = settings::registry::load()?;
+    let imported = profiles::import::import_plist(&bytes, file, domain, &defs)?;
+    save_imported(file, name, imported)
+}
+
+fn cmd_profile_import_script(file: &str, name: Option<String>) -> Result<()> {
+    let script = std::fs::read_to_string(file).context(format!("reading file '{file}'"))?;
+    let imported = profiles::script::parse_script(&script, &settings::registry::load()?);
+    save_imported(file, name, imported)
+}
+
+/// Lists what was skipped and saves the rest as a profile named `name`, or
+/// after the file.
+fn save_imported(
+    file: &str,
+    name: Option<String>,
+    imported: profiles::import::Imported,
+) -> Result<()> {
+    for skipped in &imported.skipped {
+        eprintln!("{file}: skipped {skipped}");
+    }
+    if imported.settings.is_empty() {
+        anyhow::bail!("no known settings found in '{file}'");
+    }
+
+    let name = name.unwrap_or_else(|| {
+        let stem = std::path::Path::new(file).file_stem().unwrap_or_default();
+        stem.to_string_lossy().trim_start_matches('.').to_string()
+    });
+    let skipped = imported.skipped.len();
+    let profile = imported.into_profile(name);
+    profiles::storage::save(&profile)?;
+    println!(
+        "Imported {} settings into profile '{}' ({skipped} entries skipped).",
+        profile.settings.len(),
+        profile.name
+    );
     Ok(())
 }
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use std::fmt::Write;
+
+use super::{Profile, export_header};
+use crate::settings::plan::plan_changes;
+use crate::settings::{SettingDef, SettingValue, ValueType};
+
+/// Renders a profile as an Ansible task list with one
+/// `community.general.osx_defaults` task per domain, host and key. Arrays
+/// with anything but strings, and dictionaries, are beyond `osx_defaults`,
+/// so those are written by running `defaults` directly.
+pub fn to_ansible(profile: &Profile, defs: &[SettingDef]) -> Result<String> {
+    let changes = profile.changes(defs);
+    let plan = plan_changes(&changes)?;
+
+    let mut out = export_header(profile);
+    for (def, _) in &changes {
+        for op in plan.operations.iter().filter(|op| op.id == def.id) {
+            let Some(value) = &op.value else { continue };
+            let host = if op.current_host { ", currentHost" } else { "" };
+            let label = profile
+                .label(def)
+                .map(|label| format!(": {label}"))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "- name: {}",
+                quote(&format!("{}{label} ({}{host})", def.description, op.domain))
+            )?;
+
+            let Some((kind, value)) = osx_defaults_value(def.value_type, value) else {
+                writeln!(out, "  ansible.builtin.command:")?;
+                writeln!(out, "    argv:")?;
+                for arg in &op.args {
+                    writeln!(out, "      - {}", quote(arg))?;
+                }
+                continue;
+            };
+            writeln!(out, "  community.general.osx_defaults:")?;
+            writeln!(out, "    domain: {}", quote(&op.domain))?;
+            if op.current_host {
+                writeln!(out, "    host: currentHost")?;
+            }
+            writeln!(out, "    key: {}", quote(&op.key))?;
+            writeln!(out, "    type: {kind}")?;
+            writeln!(out, "    value: {value}")?;
+            writeln!(out, "    state: present")?;
+        }
+    }
+    Ok(out)
+}
+
+/// The `type:` and YAML `value:` for a setting, if `osx_defaults` can hold it.
+fn osx_defaults_value(
+    value_type: ValueType,
+    value: &SettingValue,
+) -> Option<(&'static str, String)> {
+    Some(match (value_type, value) {
+        (ValueType::Bool, SettingValue::Bool(v)) => ("bool", v.to_string()),
+        (ValueType::Bool, SettingValue::Int(v)) => ("bool", (*v != 0).to_string()),
+        (ValueType::Int, SettingValue::Int(v)) => ("int", v.to_string()),
+        (ValueType::Int, SettingValue::Bool(v)) => ("int", i64::from(*v).to_string()),
+        (ValueType::Float, SettingValue::Float(v)) => ("float", format!("{v:?}")),
+        (ValueType::Float, SettingValue::Int(v)) => ("float", format!("{:?}", *v as f64)),
+        (ValueType::Str, SettingValue::Str(v)) => ("string", quote(v)),
+        (ValueType::Array, SettingValue::Array(items)) => {
+            let strings: Option<Vec<_>> = items
+                .iter()
+                .map(|item| match item {
+                    SettingValue::Str(s) => Some(quote(s)),
+                    _ => None,
+                })
+                .collect();
+            ("array", format!("[{}]", strings?.join(", ")))
+        }
+        _ => return None,
+    })
+}
+
+/// A double-quoted YAML scalar. JSON strings are valid YAML.
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::work_profile;
+    use crate::settings::registry::all_settings;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn writes_a_task_per_location() {
+        let replacement = SettingValue::Dict(BTreeMap::from([
+            ("replace".to_string(), SettingValue::Str("omw".into())),
+            ("with".to_string(), SettingValue::Str("On my way!".into())),
+        ]));
+        let mut profile = work_profile();
+        profile.settings.insert(
+            "text_replacements".to_string(),
+            SettingValue::Array(vec![replacement]),
+        );
+        let yaml = to_ansible(&profile, &all_settings()).unwrap();
+
+        assert_eq!(yaml.matches("community.general.osx_defaults:").count(), 5);
+        assert!(yaml.contains(
+            "    domain: \"com.apple.driver.AppleBluetoothMultitouch.trackpad\"\n    key: \"Clicking\"\n    type: bool\n    value: true\n"
+        ));
+        assert!(yaml.contains("    host: currentHost\n    key: \"com.apple.mouse.tapBehavior\"\n    type: int\n    value: 1\n"));
+        assert!(yaml.contains("- name: \"Tap to click: On (NSGlobalDomain, currentHost)\"\n"));
+        assert!(yaml.contains("  ansible.builtin.command:\n"));
+        assert!(yaml.contains("      - \"<array><dict><key>replace</key><string>omw</string>"));
+    }
+}
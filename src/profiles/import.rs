@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use super::Profile;
+use crate::settings::plist_value::to_setting_value;
+use crate::settings::{Host, HostScope, SettingDef, SettingValue};
+
+/// Something in an imported file that couldn't be turned into a setting.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    /// Line number, for formats that have meaningful lines.
+    pub line: Option<usize>,
+    pub entry: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        write!(f, "`{}`: {}", self.entry, self.reason)
+    }
+}
+
+/// Settings gathered from a foreign format, ready to become a profile.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub settings: HashMap<String, SettingValue>,
+    pub skipped: Vec<Skipped>,
+    hosts: HashMap<String, BTreeSet<Host>>,
+}
+
+impl Imported {
+    /// Maps a domain and key to its registry setting, mirror domains
+    /// included, and records the value. Later values of a setting win.
+    pub fn add(
+        &mut self,
+        defs: &[SettingDef],
+        domain: &str,
+        host: Host,
+        key: &str,
+        value: &plist::Value,
+    ) -> Result<(), String> {
+        let def = defs
+            .iter()
+            .find(|d| {
+                d.key == key && (d.domain == domain || d.mirror_domains.iter().any(|m| m == domain))
+            })
+            .ok_or("not in the registry")?;
+        let value =
+            to_setting_value(value, def.value_type).map_err(|e| format!("{}: {e}", def.id))?;
+        self.settings.insert(def.id.to_string(), value);
+        self.hosts
+            .entry(def.id.to_string())
+            .or_default()
+            .insert(host);
+        Ok(())
+    }
+
+    /// A profile of everything imported. Settings only seen on the current
+    /// host keep that scope.
+    pub fn into_profile(self, name: String) -> Profile {
+        let mut profile = Profile::new(name, self.settings);
+        for (id, hosts) in self.hosts {
+            let scope = match (hosts.contains(&Host::Any), hosts.contains(&Host::Current)) {
+                (true, true) => HostScope::Both,
+                (false, true) => HostScope::CurrentHost,
+                _ => continue,
+            };
+            profile.host_scopes.insert(id, scope);
+        }
+        profile
+    }
+}
+
+/// Imports a configuration profile or a preferences plist, XML or binary,
+/// telling them apart by content. A plain plist holds one domain, named by
+/// `domain` or else by its file name.
+pub fn import_plist(
+    bytes: &[u8],
+    file_name: &str,
+    domain: Option<&str>,
+    defs: &[SettingDef],
+) -> Result<Imported> {
+    let root = plist::Value::from_reader(std::io::Cursor::new(plist_bytes(bytes)))
+        .context("not a plist or configuration profile")?;
+    let dict = root
+        .as_dictionary()
+        .context("the plist is not a dictionary")?;
+
+    let mut imported = Imported::default();
+    if let Some(payloads) = dict.get("PayloadContent").and_then(plist::Value::as_array) {
+        for payload in payloads.iter().filter_map(plist::Value::as_dictionary) {
+            import_payload(&mut imported, payload, defs);
+        }
+    } else {
+        let (domain, host) = match domain {
+            Some(domain) => (domain.to_string(), Host::Any),
+            None => domain_from_file_name(file_name),
+        };
+        import_domain(&mut imported, &domain, host, dict, defs);
+    }
+    Ok(imported)
+}
+
+/// Signed configuration profiles wrap the XML in CMS. The content isn't
+/// encrypted, so the plist can be cut out without verifying the signature.
+fn plist_bytes(bytes: &[u8]) -> &[u8] {
+    if bytes.starts_with(b"bplist") {
+        return bytes;
+    }
+    let find = |needle: &[u8]| bytes.windows(needle.len()).position(|w| w == needle);
+    let rfind = |needle: &[u8]| bytes.windows(needle.len()).rposition(|w| w == needle);
+    match (find(b"<?xml"), rfind(b"</plist>")) {
+        (Some(start), Some(end)) if start < end => &bytes[start..end + b"</plist>".len()],
+        _ => bytes,
+    }
+}
+
+/// Reads `com.apple.ManagedClient.preferences` payloads, and custom settings
+/// payloads whose type is the domain itself.
+fn import_payload(imported: &mut Imported, payload: &plist::Dictionary, defs: &[SettingDef]) {
+    let Some(payload_type) = payload.get("PayloadType").and_then(plist::Value::as_string) else {
+        return;
+    };
+    if payload_type != "com.apple.ManagedClient.preferences" {
+        let settings: plist::Dictionary = payload
+            .iter()
+            .filter(|(key, _)| !key.starts_with("Payload"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        import_domain(imported, payload_type, Host::Any, &settings, defs);
+        return;
+    }
+
+    let Some(content) = payload
+        .get("PayloadContent")
+        .and_then(plist::Value::as_dictionary)
+    else {
+        return;
+    };
+    for (domain, management) in content {
+        let Some(management) = management.as_dictionary() else {
+            continue;
+        };
+        // Forced, Set-Once and Often each hold a list of setting groups.
+        let groups = management
+            .values()
+            .filter_map(plist::Value::as_array)
+            .flatten()
+            .filter_map(|group| {
+                group
+                    .as_dictionary()?
+                    .get("mcx_preference_settings")?
+                    .as_dictionary()
+            });
+        for settings in groups {
+            import_domain(imported, domain, Host::Any, settings, defs);
+        }
+    }
+}
+
+fn import_domain(
+    imported: &mut Imported,
+    domain: &str,
+    host: Host,
+    settings: &plist::Dictionary,
+    defs: &[SettingDef],
+) {
+    let domain = match domain {
+        ".GlobalPreferences" | "com.apple.GlobalPreferences" => "NSGlobalDomain",
+        domain => domain,
+    };
+    for (key, value) in settings {
+        if let Err(reason) = imported.add(defs, domain, host, key, value) {
+            imported.skipped.push(Skipped {
+                line: None,
+                entry: format!("{domain} {key}"),
+                reason,
+            });
+        }
+    }
+}
+
+/// `com.apple.dock.plist`, or `ByHost/com.apple.dock.<hardware UUID>.plist`
+/// for current-host preferences.
+fn domain_from_file_name(file_name: &str) -> (String, Host) {
+    let name = file_name.rsplit('/').next().unwrap_or(file_name);
+    let stem = name.strip_suffix(".plist").unwrap_or(name);
+    if let Some((domain, suffix)) = stem.rsplit_once('.')
+        && suffix.len() == 36
+        && suffix.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+    {
+        return (domain.to_string(), Host::Current);
+    }
+    (stem.to_string(), Host::Any)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::mobileconfig::{IDENTIFIER, to_mobileconfig};
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn round_trips_a_mobileconfig() {
+        let defs = all_settings();
+        let exported = include_bytes!("fixtures/work.mobileconfig");
+        // Signed profiles carry the same XML inside a CMS envelope.
+        let signed = [b"\x30\x80garbage".as_slice(), exported, b"\x00\x00trailer"].concat();
+
+        let imported = import_plist(&signed, "work.mobileconfig", None, &defs).unwrap();
+        assert!(imported.skipped.is_empty(), "{:?}", imported.skipped);
+        let profile = imported.into_profile("work".to_string());
+        assert_eq!(profile.settings["key_repeat"], SettingValue::Int(2));
+        assert_eq!(profile.settings["tp_clicking"], SettingValue::Bool(true));
+        assert_eq!(profile.settings["tap_to_click"], SettingValue::Int(1));
+        assert!(to_mobileconfig(&profile, &defs, IDENTIFIER).is_ok());
+    }
+
+    #[test]
+    fn imports_binary_byhost_plist_and_lists_unknown_keys() {
+        let defs = all_settings();
+        let mut dict = plist::Dictionary::new();
+        dict.insert(
+            "com.apple.mouse.tapBehavior".to_string(),
+            plist::Value::Integer(1.into()),
+        );
+        dict.insert("AppleUnknownKey".to_string(), plist::Value::Boolean(true));
+        let mut bytes = Vec::new();
+        plist::Value::Dictionary(dict)
+            .to_writer_binary(&mut bytes)
+            .unwrap();
+
+        let file = "ByHost/.GlobalPreferences.0D1E2F3A-4B5C-6D7E-8F90-A1B2C3D4E5F6.plist";
+        let imported = import_plist(&bytes, file, None, &defs).unwrap();
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!(imported.skipped[0].entry, "NSGlobalDomain AppleUnknownKey");
+        let profile = imported.into_profile("mouse".to_string());
+        assert_eq!(profile.settings["tap_to_click"], SettingValue::Int(1));
+        assert_eq!(profile.host_scopes["tap_to_click"], HostScope::CurrentHost);
+    }
+}
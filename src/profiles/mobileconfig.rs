@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::Profile;
+use crate::settings::SettingDef;
+use crate::settings::plan::{Action, plan_changes};
+use crate::settings::plist_value::to_plist;
+
+/// The reverse-DNS prefix of payload identifiers unless `--identifier` gives
+/// the organization's own.
+pub const IDENTIFIER: &str = "com.github.vhotsyk.myshenyatko";
+
+/// Renders a profile as an unsigned configuration profile with one
+/// `com.apple.ManagedClient.preferences` payload per domain, mirrors
+/// included. Managed preferences apply to every host, so current-host writes
+/// share their domain's payload. UUIDs are derived from the profile name and
+/// domain, so exporting again updates the same profile in an MDM. Payload
+/// identifiers start with `identifier`.
+pub fn to_mobileconfig(
+    profile: &Profile,
+    defs: &[SettingDef],
+    identifier: &str,
+) -> Result<Vec<u8>> {
+    if identifier.is_empty() || identifier_segment(identifier) != identifier {
+        anyhow::bail!(
+            "identifier '{identifier}' may only contain letters, digits, dots and dashes"
+        );
+    }
+    let name = identifier_segment(&profile.name);
+    let plan = plan_changes(&profile.changes(defs))?;
+    let mut domains: BTreeMap<&str, BTreeMap<&str, plist::Value>> = BTreeMap::new();
+    for op in &plan.operations {
+        if let (Action::Write, Some(value)) = (op.action, &op.value) {
+            domains
+                .entry(managed_domain(&op.domain))
+                .or_default()
+                .insert(&op.key, to_plist(value));
+        }
+    }
+
+    let payloads = domains
+        .into_iter()
+        .map(|(domain, settings)| {
+            let settings: plist::Dictionary = settings
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+            let forced = dict([(
+                "mcx_preference_settings",
+                plist::Value::Dictionary(settings),
+            )]);
+            let content = dict([(
+                domain,
+                dict([("Forced", plist::Value::Array(vec![forced]))]),
+            )]);
+            dict([
+                ("PayloadContent", content),
+                ("PayloadDisplayName", string(domain)),
+                ("PayloadEnabled", plist::Value::Boolean(true)),
+                (
+                    "PayloadIdentifier",
+                    string(&format!(
+                        "{identifier}.{name}.{}",
+                        domain.trim_start_matches('.')
+                    )),
+                ),
+                ("PayloadType", string("com.apple.ManagedClient.preferences")),
+                (
+                    "PayloadUUID",
+                    string(&stable_uuid(&format!("{}/{domain}", profile.name))),
+                ),
+                ("PayloadVersion", plist::Value::Integer(1.into())),
+            ])
+        })
+        .collect();
+
+    let root = dict([
+        ("PayloadContent", plist::Value::Array(payloads)),
+        (
+            "PayloadDescription",
+            string(&format!(
+                "Settings from the myshenyatko profile '{}'.",
+                profile.name
+            )),
+        ),
+        (
+            "PayloadDisplayName",
+            string(&format!("myshenyatko: {}", profile.name)),
+        ),
+        ("PayloadIdentifier", string(&format!("{identifier}.{name}"))),
+        ("PayloadScope", string("User")),
+        ("PayloadType", string("Configuration")),
+        ("PayloadUUID", string(&stable_uuid(&profile.name))),
+        ("PayloadVersion", plist::Value::Integer(1.into())),
+    ]);
+    let mut out = Vec::new();
+    root.to_writer_xml(&mut out)?;
+    out.push(b'\n');
+    Ok(out)
+}
+
+/// Signs a configuration profile with a certificate from the keychain.
+pub fn sign(profile: &[u8], identity: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("security")
+        .args(["cms", "-S", "-N", identity])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to execute security")?;
+    child.stdin.take().context("no stdin")?.write_all(profile)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "signing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// `s` with anything but letters, digits, dots and dashes replaced by a dash,
+/// as payload identifiers are reverse-DNS names.
+fn identifier_segment(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Managed preferences name the global domain by its plist file.
+fn managed_domain(domain: &str) -> &str {
+    if domain == "NSGlobalDomain" {
+        ".GlobalPreferences"
+    } else {
+        domain
+    }
+}
+
+fn dict<const N: usize>(entries: [(&str, plist::Value); N]) -> plist::Value {
+    plist::Value::Dictionary(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn string(s: &str) -> plist::Value {
+    plist::Value::String(s.to_string())
+}
+
+/// A version 8 (custom) UUID built from two FNV-1a hashes of `seed`.
+fn stable_uuid(seed: &str) -> String {
+    let fnv = |salt: u8| {
+        std::iter::once(salt)
+            .chain(seed.bytes())
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+    };
+    let high = (fnv(0) & !0xf000) | 0x8000;
+    let low = (fnv(1) & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:04X}-{:012X}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::work_profile;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn matches_fixture() {
+        let profile = work_profile();
+
+        let exported = to_mobileconfig(&profile, &all_settings(), IDENTIFIER).unwrap();
+        assert_eq!(
+            String::from_utf8(exported.clone()).unwrap(),
+            include_str!("fixtures/work.mobileconfig")
+        );
+
+        let root = plist::Value::from_reader_xml(exported.as_slice()).unwrap();
+        let payloads = root.as_dictionary().unwrap()["PayloadContent"]
+            .as_array()
+            .unwrap();
+        // The global domain plus the trackpad domain and its mirror.
+        assert_eq!(payloads.len(), 3);
+        for payload in payloads {
+            let payload = payload.as_dictionary().unwrap();
+            assert_eq!(
+                payload["PayloadType"].as_string(),
+                Some("com.apple.ManagedClient.preferences")
+            );
+        }
+    }
+
+    #[test]
+    fn every_payload_is_identified() {
+        let mut profile = work_profile();
+        profile.name = "Work laptop/ü\n".to_string();
+        let exported = to_mobileconfig(&profile, &all_settings(), "com.example.it").unwrap();
+
+        let root = plist::Value::from_reader_xml(exported.as_slice())
+            .unwrap()
+            .into_dictionary()
+            .unwrap();
+        let mut payloads = vec![&root];
+        payloads.extend(
+            root["PayloadContent"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p.as_dictionary().unwrap()),
+        );
+        let mut uuids = Vec::new();
+        for payload in payloads {
+            assert_eq!(payload["PayloadVersion"].as_signed_integer(), Some(1));
+            let identifier = payload["PayloadIdentifier"].as_string().unwrap();
+            assert!(
+                identifier.starts_with("com.example.it.Work-laptop---"),
+                "{identifier}"
+            );
+            assert!(
+                identifier
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+            );
+            uuids.push(payload["PayloadUUID"].as_string().unwrap());
+        }
+        let count = uuids.len();
+        uuids.sort();
+        uuids.dedup();
+        assert_eq!(uuids.len(), count);
+
+        assert!(to_mobileconfig(&profile, &all_settings(), "com.example it").is_err());
+    }
+
+    #[test]
+    fn uuids_are_stable_and_well_formed() {
+        let uuid = stable_uuid("work");
+        assert_eq!(uuid, stable_uuid("work"));
+        assert_ne!(uuid, stable_uuid("home"));
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "8");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "A" | "B"));
+    }
+}
@@ -1,16 +1,29 @@
+pub mod ansible;
+pub mod import;
+pub mod mobileconfig;
+pub mod nix;
+pub mod script;
+pub mod shell;
 pub mod storage;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::settings::SettingValue;
+use crate::settings::{HostScope, SettingChange, SettingDef, SettingValue};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub settings: HashMap<String, SettingValue>,
+    /// Host scope of every setting that isn't stored in the any-host domain.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub host_scopes: HashMap<String, HostScope>,
+    /// Names of the values of settings that have them. Only written on
+    /// export, for people reading the file.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
 }
 
 impl Profile {
@@ -19,6 +32,97 @@ impl Profile {
             name,
             created_at: Utc::now(),
             settings,
+            host_scopes: HashMap::new(),
+            labels: HashMap::new(),
         }
     }
+
+    pub fn record_host_scopes(&mut self, defs: &[SettingDef]) {
+        self.host_scopes = defs
+            .iter()
+            .filter(|d| self.settings.contains_key(&*d.id) && !d.host_scope.is_any_host())
+            .map(|d| (d.id.to_string(), d.host_scope))
+            .collect();
+    }
+
+    /// The name of this profile's value for `def`, if it has one.
+    pub fn label<'a>(&self, def: &'a SettingDef) -> Option<&'a str> {
+        def.label(self.settings.get(&*def.id)?)
+    }
+
+    pub fn record_labels(&mut self, defs: &[SettingDef]) {
+        self.labels = defs
+            .iter()
+            .filter_map(|def| Some((def.id.to_string(), self.label(def)?.to_string())))
+            .collect();
+    }
+
+    /// Puts the host scopes this profile was saved with onto `defs`, so its
+    /// values are written back where they were read from.
+    pub fn apply_host_scopes(&self, defs: &mut [SettingDef]) {
+        for def in defs {
+            if let Some(scope) = self.host_scopes.get(&*def.id) {
+                def.host_scope = *scope;
+            }
+        }
+    }
+
+    /// The changes applying this profile makes, ordered by setting id.
+    /// Settings missing from `defs` are skipped.
+    pub fn changes<'a>(&self, defs: &'a [SettingDef]) -> Vec<(&'a SettingDef, SettingChange)> {
+        let mut changes: Vec<_> = self
+            .settings
+            .iter()
+            .filter_map(|(id, value)| {
+                defs.iter()
+                    .find(|d| d.id == *id)
+                    .map(|def| (def, SettingChange::Set(value.clone())))
+            })
+            .collect();
+        changes.sort_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+        changes
+    }
+}
+
+/// The comment that opens exported files. Control characters in the name,
+/// such as a newline that would end the comment, are replaced.
+pub fn export_header(profile: &Profile) -> String {
+    let name: String = profile
+        .name
+        .chars()
+        .map(|c| if c.is_control() { '?' } else { c })
+        .collect();
+    format!("# Profile '{name}', exported by myshenyatko.\n")
+}
+
+/// The profile the export tests share.
+#[cfg(test)]
+pub fn work_profile() -> Profile {
+    use crate::settings::SettingValue;
+    Profile::new(
+        "work".to_string(),
+        HashMap::from([
+            ("tp_clicking".to_string(), SettingValue::Bool(true)),
+            ("key_repeat".to_string(), SettingValue::Int(2)),
+            ("tap_to_click".to_string(), SettingValue::Int(1)),
+        ]),
+    )
+}
+
+/// What `profile export` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// The profile itself, for `profile import`
+    #[default]
+    Json,
+    /// A POSIX shell script of `defaults` commands
+    Sh,
+    /// A configuration profile for MDM
+    Mobileconfig,
+    /// A nix-darwin module setting `system.defaults`
+    Nix,
+    /// A Home Manager module setting `targets.darwin.defaults`
+    HomeManager,
+    /// An Ansible task list using `community.general.osx_defaults`
+    Ansible,
 }
@@ -0,0 +1,261 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{Profile, export_header};
+use crate::settings::nix_options::nix_darwin_option;
+use crate::settings::plan::plan_changes;
+use crate::settings::{SettingDef, SettingValue, ValueType};
+
+/// Domain → key → value and its name, if it has one.
+type Domains<'a> = BTreeMap<String, BTreeMap<String, (SettingValue, Option<&'a str>)>>;
+
+/// Renders a profile as a nix-darwin module. Settings with a typed option
+/// use it; mirror domains and everything else go to `CustomUserPreferences`.
+/// nix-darwin can't write current-host preferences, so those are listed in
+/// a comment.
+pub fn to_nix_darwin(profile: &Profile, defs: &[SettingDef]) -> Result<String> {
+    let changes = profile.changes(defs);
+    let plan = plan_changes(&changes)?;
+    let mut typed = Vec::new();
+    let mut custom = Domains::new();
+    let mut current_host = Vec::new();
+    for (def, _) in &changes {
+        let option = nix_darwin_option(&def.id);
+        let label = profile.label(def);
+        for op in plan.operations.iter().filter(|op| op.id == def.id) {
+            let Some(value) = &op.value else { continue };
+            match option {
+                _ if op.current_host => current_host.push(format!("{} {}", op.domain, op.key)),
+                Some(path) if op.domain == def.domain => {
+                    typed.push((path, as_type(def.value_type, value), label))
+                }
+                _ => {
+                    custom
+                        .entry(op.domain.clone())
+                        .or_default()
+                        .insert(op.key.clone(), (value.clone(), label));
+                }
+            }
+        }
+    }
+
+    let mut out = export_header(profile);
+    for entry in &current_host {
+        writeln!(out, "# Current-host setting left out: {entry}")?;
+    }
+    writeln!(out, "{{")?;
+    writeln!(out, "  system.defaults = {{")?;
+    for (path, value, label) in typed {
+        let path: Vec<_> = path.iter().map(|segment| attr_name(segment)).collect();
+        writeln!(
+            out,
+            "    {} = {};{}",
+            path.join("."),
+            nix_value(&value),
+            comment(label)
+        )?;
+    }
+    if !custom.is_empty() {
+        writeln!(out, "    CustomUserPreferences = {{")?;
+        write_domains(&mut out, &custom, "      ")?;
+        writeln!(out, "    }};")?;
+    }
+    writeln!(out, "  }};")?;
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+/// Renders a profile as a Home Manager module, using
+/// `targets.darwin.defaults` and `targets.darwin.currentHostDefaults`.
+pub fn to_home_manager(profile: &Profile, defs: &[SettingDef]) -> Result<String> {
+    let changes = profile.changes(defs);
+    let plan = plan_changes(&changes)?;
+    let mut any_host = Domains::new();
+    let mut current_host = Domains::new();
+    for (def, _) in &changes {
+        let label = profile.label(def);
+        for op in plan.operations.iter().filter(|op| op.id == def.id) {
+            let Some(value) = &op.value else { continue };
+            let domains = if op.current_host {
+                &mut current_host
+            } else {
+                &mut any_host
+            };
+            domains
+                .entry(op.domain.clone())
+                .or_default()
+                .insert(op.key.clone(), (value.clone(), label));
+        }
+    }
+
+    let mut out = export_header(profile);
+    writeln!(out, "{{")?;
+    for (option, domains) in [
+        ("defaults", any_host),
+        ("currentHostDefaults", current_host),
+    ] {
+        if domains.is_empty() {
+            continue;
+        }
+        writeln!(out, "  targets.darwin.{option} = {{")?;
+        write_domains(&mut out, &domains, "    ")?;
+        writeln!(out, "  }};")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+fn write_domains(out: &mut String, domains: &Domains, indent: &str) -> std::fmt::Result {
+    for (domain, keys) in domains {
+        writeln!(out, "{indent}{} = {{", attr_name(domain))?;
+        for (key, (value, label)) in keys {
+            writeln!(
+                out,
+                "{indent}  {} = {};{}",
+                attr_name(key),
+                nix_value(value),
+                comment(*label)
+            )?;
+        }
+        writeln!(out, "{indent}}};")?;
+    }
+    Ok(())
+}
+
+fn comment(label: Option<&str>) -> String {
+    label.map(|label| format!(" # {label}")).unwrap_or_default()
+}
+
+/// Quotes attribute names that aren't plain Nix identifiers.
+fn attr_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+    if plain {
+        name.to_string()
+    } else {
+        nix_string(name)
+    }
+}
+
+/// Converts between the number and bool variants `check_type` lets a
+/// setting hold, since typed options reject the others. A fractional float
+/// is left alone rather than truncated.
+fn as_type(value_type: ValueType, value: &SettingValue) -> SettingValue {
+    match (value_type, value) {
+        (ValueType::Bool, SettingValue::Int(v)) => SettingValue::Bool(*v != 0),
+        (ValueType::Bool, SettingValue::Float(v)) => SettingValue::Bool(*v != 0.0),
+        (ValueType::Int, SettingValue::Bool(v)) => SettingValue::Int(i64::from(*v)),
+        (ValueType::Int, SettingValue::Float(v)) if v.fract() == 0.0 => {
+            SettingValue::Int(*v as i64)
+        }
+        (ValueType::Float, SettingValue::Int(v)) => SettingValue::Float(*v as f64),
+        (ValueType::Float, SettingValue::Bool(v)) => SettingValue::Float(f64::from(u8::from(*v))),
+        _ => value.clone(),
+    }
+}
+
+fn nix_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+        .replace('\n', r"\n");
+    format!("\"{escaped}\"")
+}
+
+fn nix_value(value: &SettingValue) -> String {
+    match value {
+        SettingValue::Bool(v) => v.to_string(),
+        SettingValue::Int(v) => v.to_string(),
+        // Nix float literals need a decimal point, also before an exponent.
+        SettingValue::Float(v) => {
+            let s = format!("{v:?}");
+            match s.split_once('e') {
+                Some((mantissa, exp)) if !mantissa.contains('.') => format!("{mantissa}.0e{exp}"),
+                _ => s,
+            }
+        }
+        SettingValue::Str(v) => nix_string(v),
+        SettingValue::Array(items) => {
+            let items: Vec<_> = items.iter().map(nix_value).collect();
+            format!("[ {} ]", items.join(" "))
+        }
+        SettingValue::Dict(entries) => {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|(k, v)| format!("{} = {};", attr_name(k), nix_value(v)))
+                .collect();
+            format!("{{ {} }}", entries.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::work_profile;
+    use crate::settings::registry::all_settings;
+
+    fn profile() -> Profile {
+        let mut profile = work_profile();
+        profile
+            .settings
+            .insert("natural_scroll".to_string(), SettingValue::Bool(false));
+        profile
+            .settings
+            .insert("mouse_tracking_speed".to_string(), SettingValue::Float(1.0));
+        profile
+    }
+
+    #[test]
+    fn uses_typed_options_and_custom_preferences() {
+        let nix = to_nix_darwin(&profile(), &all_settings()).unwrap();
+        assert!(nix.contains("    NSGlobalDomain.KeyRepeat = 2;\n"));
+        assert!(nix.contains("    NSGlobalDomain.\"com.apple.swipescrolldirection\" = false;\n"));
+        assert!(nix.contains("    \".GlobalPreferences\".\"com.apple.mouse.scaling\" = 1.0;\n"));
+        assert!(nix.contains("    trackpad.Clicking = true;\n"));
+        assert!(nix.contains(
+            "      \"com.apple.driver.AppleBluetoothMultitouch.trackpad\" = {\n        Clicking = true;\n      };\n"
+        ));
+        assert!(nix.contains(
+            "      NSGlobalDomain = {\n        \"com.apple.mouse.tapBehavior\" = 1; # On\n"
+        ));
+        assert!(nix.contains(
+            "# Current-host setting left out: NSGlobalDomain com.apple.mouse.tapBehavior\n"
+        ));
+    }
+
+    #[test]
+    fn typed_options_get_the_setting_type() {
+        let mut profile = profile();
+        profile
+            .settings
+            .insert("natural_scroll".to_string(), SettingValue::Int(0));
+        profile
+            .settings
+            .insert("mouse_tracking_speed".to_string(), SettingValue::Int(2));
+        let nix = to_nix_darwin(&profile, &all_settings()).unwrap();
+        assert!(nix.contains("    NSGlobalDomain.\"com.apple.swipescrolldirection\" = false;\n"));
+        assert!(nix.contains("    \".GlobalPreferences\".\"com.apple.mouse.scaling\" = 2.0;\n"));
+    }
+
+    #[test]
+    fn home_manager_splits_current_host() {
+        let nix = to_home_manager(&profile(), &all_settings()).unwrap();
+        let (any_host, current_host) = nix
+            .split_once("targets.darwin.currentHostDefaults")
+            .unwrap();
+        assert!(any_host.contains("KeyRepeat = 2;"));
+        assert!(current_host.contains("\"com.apple.mouse.tapBehavior\" = 1;"));
+        assert!(!current_host.contains("KeyRepeat"));
+        assert_eq!(nix_value(&SettingValue::Float(1e-7)), "1.0e-7");
+        assert_eq!(
+            nix_value(&SettingValue::Str("a\"${b}".into())),
+            r#""a\"\${b}""#
+        );
+    }
+}
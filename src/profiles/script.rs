@@ -0,0 +1,297 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::import::{Imported, Skipped};
+use crate::settings::plan::shell_line;
+use crate::settings::{Host, SettingDef};
+
+struct Write {
+    domain: String,
+    host: Host,
+    key: String,
+    value: plist::Value,
+}
+
+/// Collects the `defaults write` commands in a script and maps each one to
+/// its registry setting. Commands other than `defaults` are ignored.
+pub fn parse_script(script: &str, defs: &[SettingDef]) -> Imported {
+    let mut import = Imported::default();
+    for (line, words) in commands(script) {
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let args = match words.as_slice() {
+            ["sudo", program, args @ ..] | [program, args @ ..]
+                if *program == "defaults" || program.ends_with("/defaults") =>
+            {
+                args
+            }
+            _ => continue,
+        };
+        let result =
+            parse_write(args).and_then(|w| import.add(defs, &w.domain, w.host, &w.key, &w.value));
+        if let Err(reason) = result {
+            import.skipped.push(Skipped {
+                line: Some(line),
+                entry: shell_line(&words),
+                reason,
+            });
+        }
+    }
+    import
+}
+
+fn parse_write(args: &[&str]) -> Result<Write, String> {
+    let mut host = Host::Any;
+    let mut args = args;
+    while let [option, rest @ ..] = args
+        && option.starts_with('-')
+    {
+        match *option {
+            "-currentHost" => host = Host::Current,
+            other => return Err(format!("`defaults {other}` isn't supported")),
+        }
+        args = rest;
+    }
+    let (domain, key, value) = match args {
+        ["write", domain, key, value @ ..] => (domain, key, value),
+        ["write", ..] => return Err("missing domain or key".to_string()),
+        [verb, ..] => return Err(format!("only `defaults write` is imported, not `{verb}`")),
+        [] => return Err("missing command".to_string()),
+    };
+    Ok(Write {
+        domain: normalize_domain(domain),
+        host,
+        key: key.to_string(),
+        value: parse_value_args(value)?,
+    })
+}
+
+/// `-g`, paths to plist files and the other spellings `defaults` accepts.
+fn normalize_domain(domain: &str) -> String {
+    match domain {
+        "-g" | "-globalDomain" | "NSGlobalDomain" | "Apple Global Domain" => {
+            "NSGlobalDomain".to_string()
+        }
+        path => {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            name.strip_suffix(".plist").unwrap_or(name).to_string()
+        }
+    }
+}
+
+fn parse_value_args(args: &[&str]) -> Result<plist::Value, String> {
+    match args {
+        [] => Err("missing value".to_string()),
+        ["-array", items @ ..] => {
+            let mut values = Vec::new();
+            let mut rest = items;
+            while !rest.is_empty() {
+                let (value, tail) = next_item(rest)?;
+                values.push(value);
+                rest = tail;
+            }
+            Ok(plist::Value::Array(values))
+        }
+        ["-dict", pairs @ ..] => {
+            let mut dict = plist::Dictionary::new();
+            let mut rest = pairs;
+            while let [key, tail @ ..] = rest {
+                if tail.is_empty() {
+                    return Err(format!("-dict key {key} has no value"));
+                }
+                let (value, tail) = next_item(tail)?;
+                dict.insert(key.to_string(), value);
+                rest = tail;
+            }
+            Ok(plist::Value::Dictionary(dict))
+        }
+        [flag @ ("-array-add" | "-dict-add" | "-data" | "-date"), ..] => {
+            Err(format!("{flag} values aren't supported"))
+        }
+        _ => match next_item(args)? {
+            (value, []) => Ok(value),
+            _ => Err("unexpected extra arguments".to_string()),
+        },
+    }
+}
+
+/// One value, typed by a flag or untyped, and the arguments after it.
+fn next_item<'a, 'b>(args: &'a [&'b str]) -> Result<(plist::Value, &'a [&'b str]), String> {
+    match args {
+        [flag, value, rest @ ..] if value_flag(flag) => Ok((scalar(flag, value)?, rest)),
+        [flag] if value_flag(flag) => Err(format!("{flag} has no value")),
+        [value, rest @ ..] => Ok((literal(value)?, rest)),
+        [] => Err("missing value".to_string()),
+    }
+}
+
+fn value_flag(flag: &str) -> bool {
+    matches!(
+        flag,
+        "-int" | "-integer" | "-float" | "-bool" | "-boolean" | "-string"
+    )
+}
+
+fn scalar(flag: &str, value: &str) -> Result<plist::Value, String> {
+    let bad = || format!("{value} is not a valid {flag} value");
+    Ok(match flag {
+        "-int" | "-integer" => {
+            plist::Value::Integer(value.parse::<i64>().map_err(|_| bad())?.into())
+        }
+        "-float" => plist::Value::Real(value.parse().map_err(|_| bad())?),
+        "-bool" | "-boolean" => plist::Value::Boolean(match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => true,
+            "false" | "no" | "0" => false,
+            _ => return Err(bad()),
+        }),
+        _ => plist::Value::String(value.to_string()),
+    })
+}
+
+/// An untyped value: an XML plist fragment, or else a string.
+fn literal(value: &str) -> Result<plist::Value, String> {
+    if !value.trim_start().starts_with('<') {
+        return Ok(plist::Value::String(value.to_string()));
+    }
+    let xml = format!("<plist version=\"1.0\">{value}</plist>");
+    plist::Value::from_reader_xml(xml.as_bytes())
+        .map_err(|e| format!("invalid plist fragment: {e}"))
+}
+
+/// Splits a script into simple commands, each with the line it starts on.
+/// Handles quotes, backslash escapes, comments, line continuations and the
+/// `;`, `&&`, `||` and `|` separators. Expansions are left as written.
+fn commands(script: &str) -> Vec<(usize, Vec<String>)> {
+    let mut out = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word: Option<String> = None;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = script.chars().peekable();
+
+    let end_word = |word: &mut Option<String>, words: &mut Vec<String>| {
+        if let Some(w) = word.take() {
+            words.push(w);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if word.is_none() && words.is_empty() && !c.is_whitespace() {
+            start = line;
+        }
+        match c {
+            '\n' | ';' | '&' | '|' => {
+                end_word(&mut word, &mut words);
+                if !words.is_empty() {
+                    out.push((start, std::mem::take(&mut words)));
+                }
+                if c == '\n' {
+                    line += 1;
+                }
+            }
+            c if c.is_whitespace() => end_word(&mut word, &mut words),
+            '#' if word.is_none() => while chars.next_if(|&c| c != '\n').is_some() {},
+            '\\' => match chars.next() {
+                Some('\n') => line += 1,
+                Some(next) => word.get_or_insert_default().push(next),
+                None => {}
+            },
+            '\'' => {
+                let w = word.get_or_insert_default();
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    line += usize::from(c == '\n');
+                    w.push(c);
+                }
+            }
+            '"' => double_quoted(&mut chars, word.get_or_insert_default(), &mut line),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    end_word(&mut word, &mut words);
+    if !words.is_empty() {
+        out.push((start, words));
+    }
+    out
+}
+
+fn double_quoted(chars: &mut Peekable<Chars>, word: &mut String, line: &mut usize) {
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return,
+            '\\' => match chars.next_if(|c| matches!(c, '$' | '`' | '"' | '\\' | '\n')) {
+                Some('\n') => *line += 1,
+                Some(escaped) => word.push(escaped),
+                None => word.push('\\'),
+            },
+            c => {
+                *line += usize::from(c == '\n');
+                word.push(c);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::registry::all_settings;
+    use crate::settings::{HostScope, SettingValue};
+
+    #[test]
+    fn imports_defaults_writes_from_a_script() {
+        let script = r#"#!/bin/sh
+# Keyboard
+defaults write -g KeyRepeat -int 2
+defaults write NSGlobalDomain InitialKeyRepeat 15 # untyped
+sudo defaults -currentHost write -g com.apple.mouse.tapBehavior -bool true
+defaults write com.apple.driver.AppleBluetoothMultitouch.trackpad Clicking \
+    -bool "YES"; killall Dock
+defaults write 'com.apple.dock' autohide -bool true
+defaults write -g KeyRepeat -string "fast"
+defaults delete -g ApplePressAndHoldEnabled
+"#;
+        let import = parse_script(script, &all_settings());
+
+        assert_eq!(import.settings["key_repeat"], SettingValue::Int(2));
+        assert_eq!(import.settings["initial_key_repeat"], SettingValue::Int(15));
+        assert_eq!(import.settings["tap_to_click"], SettingValue::Int(1));
+        assert_eq!(import.settings["tp_clicking"], SettingValue::Bool(true));
+        assert_eq!(import.settings.len(), 4);
+
+        let skipped: Vec<_> = import
+            .skipped
+            .iter()
+            .map(|s| (s.line, s.entry.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (Some(8), "defaults write com.apple.dock autohide -bool true"),
+                (Some(9), "defaults write -g KeyRepeat -string fast"),
+                (Some(10), "defaults delete -g ApplePressAndHoldEnabled"),
+            ]
+        );
+        assert_eq!(import.skipped[0].reason, "not in the registry");
+        let profile = import.into_profile("macos".to_string());
+        assert_eq!(profile.host_scopes["tap_to_click"], HostScope::CurrentHost);
+    }
+
+    #[test]
+    fn reads_collections() {
+        let script = r#"defaults write -g NSUserDictionaryReplacementItems '<array><dict><key>replace</key><string>omw</string><key>with</key><string>On my way!</string></dict></array>'"#;
+        let import = parse_script(script, &all_settings());
+        let SettingValue::Array(items) = &import.settings["text_replacements"] else {
+            panic!("expected an array");
+        };
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            parse_value_args(&["-array", "-int", "1", "two"]).unwrap(),
+            plist::Value::Array(vec![
+                plist::Value::Integer(1.into()),
+                plist::Value::String("two".into())
+            ])
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use std::fmt::Write;
+
+use super::{Profile, export_header};
+use crate::settings::SettingDef;
+use crate::settings::plan::plan_changes;
+
+/// Renders a profile as a POSIX script of `defaults` commands, each setting
+/// commented with its description and help. Running it again changes nothing.
+pub fn to_shell(profile: &Profile, defs: &[SettingDef]) -> Result<String> {
+    let changes = profile.changes(defs);
+    let plan = plan_changes(&changes)?;
+
+    let mut out = String::new();
+    writeln!(out, "#!/bin/sh")?;
+    out.push_str(&export_header(profile));
+    writeln!(out, "set -e")?;
+    for (def, _) in &changes {
+        writeln!(out)?;
+        match profile.label(def) {
+            Some(label) => writeln!(out, "# {}: {label}", def.description)?,
+            None => writeln!(out, "# {}", def.description)?,
+        }
+        for line in def.help.lines() {
+            writeln!(out, "# {line}")?;
+        }
+        for op in plan.operations.iter().filter(|op| op.id == def.id) {
+            writeln!(out, "{op}")?;
+        }
+    }
+
+    if !plan.activations.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "# Make the new values take effect.")?;
+        for command in &plan.activations {
+            writeln!(out, "{command} || true")?;
+        }
+    }
+    let logout: Vec<_> = changes
+        .iter()
+        .filter(|(def, _)| def.requires_logout())
+        .map(|(def, _)| &*def.description)
+        .collect();
+    if !logout.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "# Log out and back in for: {}", logout.join(", "))?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::work_profile;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn writes_commented_commands_and_activation() {
+        let defs = all_settings();
+        let mut profile = work_profile();
+        profile.name = "work\nrm -rf ~".to_string();
+        let script = to_shell(&profile, &defs).unwrap();
+
+        assert!(
+            script.starts_with("#!/bin/sh\n# Profile 'work?rm -rf ~', exported by myshenyatko.\n")
+        );
+        let clicking = defs.iter().find(|d| d.id == "tp_clicking").unwrap();
+        for domain in std::iter::once(&clicking.domain).chain(&clicking.mirror_domains) {
+            assert!(script.contains(&format!("defaults write {domain} Clicking -bool TRUE\n")));
+        }
+        assert!(script.contains(&format!("# {}\n", clicking.description)));
+        assert!(script.contains("defaults write NSGlobalDomain KeyRepeat -int 2\n"));
+        assert!(script.contains("activateSettings -u || true\n"));
+        assert!(script.ends_with("# Log out and back in for: Key repeat rate (lower = faster)\n"));
+    }
+}
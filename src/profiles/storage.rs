@@ -3,6 +3,7 @@ use std::fs;
 use std::path::PathBuf;
 
 use super::Profile;
+use crate::settings::SettingDef;
 
 fn profiles_dir() -> Result<PathBuf> {
     let config = dirs::config_dir().context("could not determine config directory")?;
@@ -14,7 +15,13 @@ fn profiles_dir() -> Result<PathBuf> {
 fn profile_path(name: &str) -> Result<PathBuf> {
     let sanitized: String = name
         .chars()
-        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
         .collect();
     Ok(profiles_dir()?.join(format!("{sanitized}.json")))
 }
@@ -39,10 +46,10 @@ pub fn list() -> Result<Vec<String>> {
     for entry in fs::read_dir(&dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.extension().is_some_and(|e| e == "json") {
-            if let Some(stem) = path.file_stem() {
-                names.push(stem.to_string_lossy().to_string());
-            }
+        if path.extension().is_some_and(|e| e == "json")
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().to_string());
         }
     }
     names.sort();
@@ -57,13 +64,20 @@ pub fn delete(name: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn export_json(name: &str) -> Result<String> {
-    let profile = load(name)?;
+/// The saved profile, with the names of its values filled in.
+pub fn export_json(name: &str, defs: &[SettingDef]) -> Result<String> {
+    let mut profile = load(name)?;
+    profile.record_labels(defs);
     Ok(serde_json::to_string_pretty(&profile)?)
 }
 
-pub fn import_json(json: &str) -> Result<Profile> {
-    let profile: Profile = serde_json::from_str(json)?;
+/// Saves an exported profile, under `name` if given.
+pub fn import_json(json: &str, name: Option<String>) -> Result<Profile> {
+    let mut profile: Profile = serde_json::from_str(json)?;
+    if let Some(name) = name {
+        profile.name = name;
+    }
+    profile.labels.clear();
     save(&profile)?;
     Ok(profile)
 }
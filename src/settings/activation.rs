@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::process::Command;
+
+use super::{Activation, SettingDef};
+
+const ACTIVATE_SETTINGS: &str =
+    "/System/Library/PrivateFrameworks/SystemAdministration.framework/Resources/activateSettings";
+
+/// Runs the external commands that make written settings take effect.
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &str, args: &[&str]) -> Result<()>;
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<()> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("failed to execute {program}"))?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+/// Runs nothing. Used with `--prefs-file`, where there's no session to update.
+pub struct NoopRunner;
+
+impl CommandRunner for NoopRunner {
+    fn run(&self, _program: &str, _args: &[&str]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Which applied settings are in effect now and which wait for a logout.
+#[derive(Debug, Clone, Default)]
+pub struct ActivationReport {
+    pub live: Vec<String>,
+    pub needs_logout: Vec<String>,
+    /// `command: error` for every activation command that failed. Settings
+    /// that depended on it are listed under `needs_logout`.
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for ActivationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} live", self.live.len())?;
+        if !self.needs_logout.is_empty() {
+            write!(f, ", {} need logout", self.needs_logout.len())?;
+        }
+        if !self.errors.is_empty() {
+            write!(f, " ({})", self.errors.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn command(activation: Activation) -> Option<(&'static str, Vec<&'static str>)> {
+    match activation {
+        Activation::Immediate | Activation::Logout => None,
+        Activation::FlushPreferences => Some(("killall", vec!["cfprefsd"])),
+        Activation::ActivateSettings => Some((ACTIVATE_SETTINGS, vec!["-u"])),
+        Activation::Restart(process) => Some(("killall", vec![process])),
+    }
+}
+
+/// The commands `activate` would run for these settings, in order.
+pub fn activation_commands(defs: &[&SettingDef]) -> Vec<(&'static str, Vec<&'static str>)> {
+    let needed: BTreeSet<Activation> = defs.iter().map(|d| d.activation).collect();
+    needed.into_iter().filter_map(command).collect()
+}
+
+/// Runs each activation the changed settings need once, least disruptive
+/// first.
+pub fn activate(runner: &dyn CommandRunner, defs: &[&SettingDef]) -> ActivationReport {
+    let needed: BTreeSet<Activation> = defs.iter().map(|d| d.activation).collect();
+    let mut failed = BTreeSet::new();
+    let mut report = ActivationReport::default();
+    for activation in needed {
+        if let Some((program, args)) = command(activation)
+            && let Err(e) = runner.run(program, &args)
+        {
+            report
+                .errors
+                .push(format!("{program} {}: {e}", args.join(" ")));
+            failed.insert(activation);
+        }
+    }
+
+    for def in defs {
+        if def.requires_logout() || failed.contains(&def.activation) {
+            report.needs_logout.push(def.id.to_string());
+        } else {
+            report.live.push(def.id.to_string());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::registry::all_settings;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        calls: Mutex<Vec<String>>,
+        fail: Option<&'static str>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<()> {
+            let call = format!("{program} {}", args.join(" "));
+            self.calls.lock().unwrap().push(call.clone());
+            if self.fail.is_some_and(|f| call.contains(f)) {
+                anyhow::bail!("no such process");
+            }
+            Ok(())
+        }
+    }
+
+    fn defs(ids: &[&str]) -> Vec<SettingDef> {
+        let all = all_settings();
+        ids.iter()
+            .map(|id| all.iter().find(|d| d.id == *id).unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn runs_each_activation_once_in_order() {
+        let defs = defs(&[
+            "tp_four_finger_pinch",
+            "tap_to_click",
+            "tp_clicking",
+            "key_repeat",
+            "smart_quotes",
+        ]);
+        let runner = RecordingRunner::default();
+
+        let report = activate(&runner, &defs.iter().collect::<Vec<_>>());
+
+        assert_eq!(
+            *runner.calls.lock().unwrap(),
+            vec![
+                format!("{ACTIVATE_SETTINGS} -u"),
+                "killall Dock".to_string()
+            ]
+        );
+        assert_eq!(
+            report.live,
+            vec![
+                "tp_four_finger_pinch",
+                "tap_to_click",
+                "tp_clicking",
+                "smart_quotes"
+            ]
+        );
+        assert_eq!(report.needs_logout, vec!["key_repeat"]);
+    }
+
+    #[test]
+    fn failed_activation_needs_logout() {
+        let defs = defs(&["tp_four_finger_pinch", "tp_clicking"]);
+        let runner = RecordingRunner {
+            fail: Some("Dock"),
+            ..Default::default()
+        };
+
+        let report = activate(&runner, &defs.iter().collect::<Vec<_>>());
+
+        assert_eq!(report.live, vec!["tp_clicking"]);
+        assert_eq!(report.needs_logout, vec!["tp_four_finger_pinch"]);
+        assert_eq!(report.errors.len(), 1);
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt;
+
+use super::backend::{PreferencesBackend, ReadError};
+use super::conflict::{Conflict, ConflictPolicy, check_conflict};
+use super::reader::read_setting;
+use super::writer::{reset_setting, write_setting};
+use super::{Host, SettingChange, SettingDef, SettingValue, ValueType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    Succeeded,
+    RolledBack,
+    RollbackFailed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApplyFailure {
+    pub id: String,
+    pub description: String,
+    pub error: String,
+}
+
+/// What happened when a batch of changes was applied.
+#[derive(Debug, Clone, Default)]
+pub struct ApplyReport {
+    /// Settings written before the batch finished or failed. After a
+    /// rollback these have been restored to their prior values.
+    pub applied: Vec<String>,
+    pub failure: Option<ApplyFailure>,
+    /// `domain.key: error` for every prior value that couldn't be restored.
+    pub rollback_errors: Vec<String>,
+    /// Settings another program changed during a guarded apply.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ApplyReport {
+    pub fn outcome(&self) -> ApplyOutcome {
+        match (&self.failure, self.rollback_errors.is_empty()) {
+            (None, _) => ApplyOutcome::Succeeded,
+            (Some(_), true) => ApplyOutcome::RolledBack,
+            (Some(_), false) => ApplyOutcome::RollbackFailed,
+        }
+    }
+}
+
+impl fmt::Display for ApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(failure) = &self.failure else {
+            return write!(f, "{} settings applied", self.applied.len());
+        };
+        write!(f, "{} failed: {}; ", failure.description, failure.error)?;
+        match self.outcome() {
+            ApplyOutcome::RollbackFailed => {
+                write!(f, "rollback failed for {}", self.rollback_errors.join(", "))
+            }
+            _ => write!(f, "all changes rolled back"),
+        }
+    }
+}
+
+struct PriorValue {
+    domain: String,
+    host: Host,
+    key: String,
+    value_type: ValueType,
+    value: Result<SettingValue, ReadError>,
+}
+
+/// Writes (or resets) every change, or none of them. The prior value of each key,
+/// including its mirror domains and current-host copies, is captured right before
+/// it is written; if any write fails everything touched so far is restored and
+/// unset keys are deleted again. Prior values that couldn't be read are left as
+/// written and reported as rollback errors.
+pub fn apply_changes(
+    backend: &dyn PreferencesBackend,
+    changes: &[(&SettingDef, SettingChange)],
+) -> ApplyReport {
+    apply(backend, changes, None)
+}
+
+/// Like `apply_changes`, but each setting is read again right before it is
+/// written and compared with what it held when the batch started. Settings
+/// another program changed in between are handled according to `policy`;
+/// `Fail` rolls back what was written so far.
+pub fn apply_guarded(
+    backend: &dyn PreferencesBackend,
+    changes: &[(&SettingDef, SettingChange)],
+    policy: ConflictPolicy,
+) -> ApplyReport {
+    apply(backend, changes, Some(policy))
+}
+
+fn apply(
+    backend: &dyn PreferencesBackend,
+    changes: &[(&SettingDef, SettingChange)],
+    policy: Option<ConflictPolicy>,
+) -> ApplyReport {
+    let seen: Vec<_> = match policy {
+        Some(_) => changes
+            .iter()
+            .map(|(def, _)| read_setting(backend, def))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut report = ApplyReport::default();
+    let mut priors: Vec<PriorValue> = Vec::new();
+    for (idx, (def, change)) in changes.iter().enumerate() {
+        if let Some(policy) = policy {
+            backend.invalidate();
+            if let Some(conflict) = check_conflict(backend, def, &seen[idx]) {
+                report.conflicts.push(conflict);
+                match policy {
+                    ConflictPolicy::Skip => continue,
+                    ConflictPolicy::Fail => {
+                        report.failure = Some(ApplyFailure {
+                            id: def.id.to_string(),
+                            description: def.description.to_string(),
+                            error: "changed by another program while applying".to_string(),
+                        });
+                        report.rollback_errors = rollback(backend, priors.iter());
+                        return report;
+                    }
+                    ConflictPolicy::Overwrite => {}
+                }
+            }
+        }
+        // The failing setting may be written to some of its domains before
+        // the error, so its prior values are restored too.
+        priors.extend(capture(backend, def));
+        let result = match change {
+            SettingChange::Set(value) => write_setting(backend, def, value),
+            SettingChange::Reset => reset_setting(backend, def),
+        };
+        if let Err(e) = result {
+            report.failure = Some(ApplyFailure {
+                id: def.id.to_string(),
+                description: def.description.to_string(),
+                error: e.to_string(),
+            });
+            report.rollback_errors = rollback(backend, priors.iter());
+            return report;
+        }
+        report.applied.push(def.id.to_string());
+    }
+    report
+}
+
+fn capture(backend: &dyn PreferencesBackend, def: &SettingDef) -> Vec<PriorValue> {
+    def.locations()
+        .into_iter()
+        .map(|(domain, host)| PriorValue {
+            domain: domain.to_string(),
+            host,
+            key: def.key.to_string(),
+            value_type: def.value_type,
+            value: backend.read(domain, host, &def.key, def.value_type),
+        })
+        .collect()
+}
+
+fn rollback<'a>(
+    backend: &dyn PreferencesBackend,
+    priors: impl Iterator<Item = &'a PriorValue>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for prior in priors {
+        let result = match &prior.value {
+            Ok(value) => backend.write(&prior.domain, prior.host, &prior.key, value),
+            // Keys the failed write never reached are still unset.
+            Err(e)
+                if e.is_not_set()
+                    && backend
+                        .read(&prior.domain, prior.host, &prior.key, prior.value_type)
+                        .is_err_and(|e| e.is_not_set()) =>
+            {
+                continue;
+            }
+            Err(e) if e.is_not_set() => backend.delete(&prior.domain, prior.host, &prior.key),
+            // Deleting would lose a value we couldn't read, so leave the key alone.
+            Err(e) => Err(anyhow::anyhow!("prior value unknown ({e})")),
+        };
+        if let Err(e) = result {
+            errors.push(format!("{}.{}: {e}", prior.domain, prior.key));
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::backend::{MemoryBackend, ReadError};
+    use crate::settings::plist_value::PlistType;
+    use crate::settings::registry::all_settings;
+    use anyhow::Result;
+
+    /// Memory backend that refuses writes to one domain, and optionally
+    /// refuses deletes too so rollback can fail.
+    struct FailingBackend {
+        inner: MemoryBackend,
+        fail_domain: String,
+        fail_deletes: bool,
+    }
+
+    impl PreferencesBackend for FailingBackend {
+        fn domain_exists(&self, domain: &str, host: Host) -> bool {
+            self.inner.domain_exists(domain, host)
+        }
+
+        fn read(
+            &self,
+            domain: &str,
+            host: Host,
+            key: &str,
+            value_type: ValueType,
+        ) -> Result<SettingValue, ReadError> {
+            self.inner.read(domain, host, key, value_type)
+        }
+
+        fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+            self.inner.stored_type(domain, host, key)
+        }
+
+        fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()> {
+            if domain == self.fail_domain {
+                anyhow::bail!("write refused");
+            }
+            self.inner.write(domain, host, key, value)
+        }
+
+        fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()> {
+            if self.fail_deletes {
+                anyhow::bail!("delete refused");
+            }
+            self.inner.delete(domain, host, key)
+        }
+    }
+
+    /// Memory backend where writing `trigger` also changes `KeyRepeat`, like
+    /// another program would in the middle of an apply.
+    struct MeddlingBackend {
+        inner: MemoryBackend,
+        trigger: &'static str,
+    }
+
+    impl PreferencesBackend for MeddlingBackend {
+        fn domain_exists(&self, domain: &str, host: Host) -> bool {
+            self.inner.domain_exists(domain, host)
+        }
+
+        fn read(
+            &self,
+            domain: &str,
+            host: Host,
+            key: &str,
+            value_type: ValueType,
+        ) -> Result<SettingValue, ReadError> {
+            self.inner.read(domain, host, key, value_type)
+        }
+
+        fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+            self.inner.stored_type(domain, host, key)
+        }
+
+        fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()> {
+            if key == self.trigger {
+                self.inner.write(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "KeyRepeat",
+                    &SettingValue::Int(9),
+                )?;
+            }
+            self.inner.write(domain, host, key, value)
+        }
+
+        fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()> {
+            self.inner.delete(domain, host, key)
+        }
+    }
+
+    fn def(defs: &[SettingDef], id: &str) -> SettingDef {
+        defs.iter().find(|d| d.id == id).unwrap().clone()
+    }
+
+    #[test]
+    fn applies_everything_on_success() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let repeat = def(&defs, "key_repeat");
+        let clicking = def(&defs, "tp_clicking");
+
+        let report = apply_changes(
+            &backend,
+            &[
+                (&repeat, SettingChange::Set(SettingValue::Int(2))),
+                (&clicking, SettingChange::Set(SettingValue::Bool(true))),
+            ],
+        );
+
+        assert_eq!(report.outcome(), ApplyOutcome::Succeeded);
+        assert_eq!(report.applied, vec!["key_repeat", "tp_clicking"]);
+        assert_eq!(
+            backend.snapshot().any_host[&*clicking.mirror_domains[0]]["Clicking"],
+            SettingValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn rolls_back_on_partial_failure() {
+        let defs = all_settings();
+        let repeat = def(&defs, "key_repeat");
+        let clicking = def(&defs, "tp_clicking");
+        let backend = FailingBackend {
+            inner: MemoryBackend::default(),
+            fail_domain: clicking.mirror_domains[0].to_string(),
+            fail_deletes: false,
+        };
+        backend
+            .inner
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Int(6),
+            )
+            .unwrap();
+
+        let report = apply_changes(
+            &backend,
+            &[
+                (&repeat, SettingChange::Reset),
+                (&clicking, SettingChange::Set(SettingValue::Bool(true))),
+            ],
+        );
+
+        assert_eq!(report.outcome(), ApplyOutcome::RolledBack);
+        assert_eq!(report.failure.as_ref().unwrap().id, "tp_clicking");
+        let store = backend.inner.snapshot().any_host;
+        assert_eq!(store["NSGlobalDomain"]["KeyRepeat"], SettingValue::Int(6));
+        assert!(!store[&*clicking.domain].contains_key("Clicking"));
+    }
+
+    #[test]
+    fn reports_rollback_failures() {
+        let defs = all_settings();
+        let clicking = def(&defs, "tp_clicking");
+        let backend = FailingBackend {
+            inner: MemoryBackend::default(),
+            fail_domain: clicking.mirror_domains[0].to_string(),
+            fail_deletes: true,
+        };
+
+        let report = apply_changes(
+            &backend,
+            &[(&clicking, SettingChange::Set(SettingValue::Bool(true)))],
+        );
+
+        assert_eq!(report.outcome(), ApplyOutcome::RollbackFailed);
+        assert_eq!(report.rollback_errors.len(), 1);
+    }
+
+    #[test]
+    fn keeps_unreadable_prior_values() {
+        let defs = all_settings();
+        let repeat = def(&defs, "key_repeat");
+        let clicking = def(&defs, "tp_clicking");
+        let backend = FailingBackend {
+            inner: MemoryBackend::default(),
+            fail_domain: clicking.mirror_domains[0].to_string(),
+            fail_deletes: false,
+        };
+        let often = SettingValue::Str("often".into());
+        backend
+            .inner
+            .write("NSGlobalDomain", Host::Any, "KeyRepeat", &often)
+            .unwrap();
+
+        let report = apply_changes(
+            &backend,
+            &[
+                (&repeat, SettingChange::Set(SettingValue::Int(2))),
+                (&clicking, SettingChange::Set(SettingValue::Bool(true))),
+            ],
+        );
+
+        assert_eq!(report.outcome(), ApplyOutcome::RollbackFailed);
+        assert_eq!(
+            report.rollback_errors,
+            ["NSGlobalDomain.KeyRepeat: prior value unknown (stored as string)"]
+        );
+        let store = backend.inner.snapshot().any_host;
+        assert_eq!(store["NSGlobalDomain"]["KeyRepeat"], SettingValue::Int(2));
+    }
+
+    #[test]
+    fn guarded_apply_notices_changes_between_reads() {
+        let defs = all_settings();
+        let delay = def(&defs, "initial_key_repeat");
+        let repeat = def(&defs, "key_repeat");
+        let changes = [
+            (&delay, SettingChange::Set(SettingValue::Int(15))),
+            (&repeat, SettingChange::Set(SettingValue::Int(2))),
+        ];
+
+        let backend = MeddlingBackend {
+            inner: MemoryBackend::default(),
+            trigger: "InitialKeyRepeat",
+        };
+        let report = apply_guarded(&backend, &changes, ConflictPolicy::Skip);
+        assert_eq!(report.outcome(), ApplyOutcome::Succeeded);
+        assert_eq!(report.applied, ["initial_key_repeat"]);
+        assert_eq!(report.conflicts[0].current, Ok(SettingValue::Int(9)));
+        assert_eq!(
+            backend.inner.snapshot().any_host["NSGlobalDomain"]["KeyRepeat"],
+            SettingValue::Int(9)
+        );
+
+        let backend = MeddlingBackend {
+            inner: MemoryBackend::default(),
+            trigger: "InitialKeyRepeat",
+        };
+        let report = apply_guarded(&backend, &changes, ConflictPolicy::Fail);
+        assert_eq!(report.outcome(), ApplyOutcome::RolledBack);
+        assert_eq!(report.failure.unwrap().id, "key_repeat");
+        let store = backend.inner.snapshot().any_host;
+        assert!(!store["NSGlobalDomain"].contains_key("InitialKeyRepeat"));
+        assert_eq!(store["NSGlobalDomain"]["KeyRepeat"], SettingValue::Int(9));
+    }
+}
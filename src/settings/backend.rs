@@ -0,0 +1,594 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use super::plist_value::{PlistType, to_plist, to_setting_value, xml_fragment};
+use super::reader::{parse_value, prints_as};
+use super::{Host, SettingValue, ValueType};
+
+/// Where preference values are read from and written to.
+pub trait PreferencesBackend: Send + Sync {
+    fn domain_exists(&self, domain: &str, host: Host) -> bool;
+    fn read(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<SettingValue, ReadError>;
+    /// The type the key is actually stored as, whatever the setting expects.
+    fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError>;
+    /// Like `read`, also returning the stored type when the value was
+    /// converted from one that doesn't stand for `value_type`.
+    fn read_coerced(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<(SettingValue, Option<PlistType>), ReadError> {
+        let value = self.read(domain, host, key, value_type)?;
+        let found = self.stored_type(domain, host, key).ok();
+        Ok((value, found.filter(|found| !found.stands_for(value_type))))
+    }
+    fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()>;
+    /// Removes the key. Deleting a key that isn't set is not an error.
+    fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()>;
+
+    /// Loads whole domains up front so later reads are answered from memory.
+    /// Backends that read key by key don't need to do anything here.
+    fn prefetch(&self, _domains: &[(&str, Host)]) -> Vec<DomainFetch> {
+        Vec::new()
+    }
+
+    /// Forgets anything cached so the next reads see changes made by other
+    /// programs.
+    fn invalidate(&self) {}
+
+    /// When the domain last changed, if that can be found out without reading
+    /// it, e.g. from its plist's modification time.
+    fn modified(&self, _domain: &str, _host: Host) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// A file's modification time. A file that doesn't exist yet reports the
+/// epoch, so creating it counts as a change.
+pub(super) fn file_modified(path: &Path) -> Option<SystemTime> {
+    match fs::metadata(path) {
+        Ok(meta) => meta.modified().ok(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(SystemTime::UNIX_EPOCH),
+        Err(_) => None,
+    }
+}
+
+/// Why a key couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadError {
+    /// The domain exists but the key isn't in it, so macOS uses its default.
+    NotSet,
+    /// Nothing has ever been written to the domain.
+    DomainMissing,
+    /// The stored value has a type that can't stand in for the expected one.
+    TypeMismatch { found: PlistType },
+    /// `defaults` printed something that doesn't parse as the expected type.
+    ParseError(String),
+    /// The backend couldn't be asked, e.g. `defaults` failed to run.
+    BackendUnavailable(String),
+}
+
+impl ReadError {
+    /// The key is absent rather than unreadable.
+    pub fn is_not_set(&self) -> bool {
+        matches!(self, ReadError::NotSet | ReadError::DomainMissing)
+    }
+
+    /// camelCase name used in `dump` output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReadError::NotSet => "notSet",
+            ReadError::DomainMissing => "domainMissing",
+            ReadError::TypeMismatch { .. } => "typeMismatch",
+            ReadError::ParseError(_) => "parseError",
+            ReadError::BackendUnavailable(_) => "backendUnavailable",
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::NotSet => write!(f, "not set"),
+            ReadError::DomainMissing => write!(f, "domain does not exist"),
+            ReadError::TypeMismatch { found } => write!(f, "stored as {found}"),
+            ReadError::ParseError(e) => write!(f, "parse error: {e}"),
+            ReadError::BackendUnavailable(e) => write!(f, "backend unavailable: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+/// How long loading one domain took during `PreferencesBackend::prefetch`.
+#[derive(Debug, Clone)]
+pub struct DomainFetch {
+    pub domain: String,
+    pub host: Host,
+    pub elapsed: Duration,
+    pub loaded: bool,
+}
+
+/// Talks to the real macOS preferences system through the `defaults` tool.
+pub struct DefaultsCliBackend;
+
+/// A `defaults` invocation, with `-currentHost` when targeting the ByHost copy.
+pub(super) fn defaults_command(host: Host) -> Command {
+    let mut cmd = Command::new("defaults");
+    if host == Host::Current {
+        cmd.arg("-currentHost");
+    }
+    cmd
+}
+
+impl PreferencesBackend for DefaultsCliBackend {
+    fn domain_exists(&self, domain: &str, host: Host) -> bool {
+        defaults_command(host)
+            .arg("read")
+            .arg(domain)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    fn read(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<SettingValue, ReadError> {
+        if matches!(value_type, ValueType::Array | ValueType::Dict) {
+            return read_exported(domain, host, key, value_type);
+        }
+        let raw = self.read_raw(domain, host, key)?;
+        parse_value(&raw, value_type).map_err(|e| ReadError::ParseError(format!("{raw:?}: {e}")))
+    }
+
+    /// Only runs `defaults read-type` when the printed value isn't spelled the
+    /// way the expected type prints, which saves a process per key.
+    fn read_coerced(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<(SettingValue, Option<PlistType>), ReadError> {
+        if matches!(value_type, ValueType::Array | ValueType::Dict) {
+            let value = self.read(domain, host, key, value_type)?;
+            return Ok((value, None));
+        }
+        let raw = self.read_raw(domain, host, key)?;
+        let value = parse_value(&raw, value_type)
+            .map_err(|e| ReadError::ParseError(format!("{raw:?}: {e}")))?;
+        if prints_as(&raw, value_type) {
+            return Ok((value, None));
+        }
+        let found = self.stored_type(domain, host, key).ok();
+        Ok((value, found.filter(|found| !found.stands_for(value_type))))
+    }
+
+    fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+        let output = defaults_command(host)
+            .arg("read-type")
+            .arg(domain)
+            .arg(key)
+            .output()
+            .map_err(|e| {
+                ReadError::BackendUnavailable(format!("failed to execute defaults: {e}"))
+            })?;
+        if !output.status.success() {
+            return Err(self.read_failure(domain, host, &output.stderr));
+        }
+
+        // Prints e.g. "Type is float".
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let name = stdout.trim().trim_start_matches("Type is ");
+        PlistType::from_name(name)
+            .ok_or_else(|| ReadError::ParseError(format!("unknown type {name:?}")))
+    }
+
+    fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()> {
+        let output = defaults_command(host)
+            .arg("write")
+            .arg(domain)
+            .arg(key)
+            .args(write_args(value))
+            .output()
+            .context("failed to execute defaults write")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "defaults write failed for {domain}.{key}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()> {
+        let output = defaults_command(host)
+            .arg("delete")
+            .arg(domain)
+            .arg(key)
+            .output()
+            .context("failed to execute defaults delete")?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success()
+            && !stderr.contains("does not exist")
+            && !stderr.contains("not found")
+        {
+            anyhow::bail!(
+                "defaults delete failed for {domain}.{key}: {}",
+                stderr.trim()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl DefaultsCliBackend {
+    /// What `defaults read` prints for the key.
+    fn read_raw(&self, domain: &str, host: Host, key: &str) -> Result<String, ReadError> {
+        let output = defaults_command(host)
+            .arg("read")
+            .arg(domain)
+            .arg(key)
+            .output()
+            .map_err(|e| {
+                ReadError::BackendUnavailable(format!("failed to execute defaults: {e}"))
+            })?;
+
+        if !output.status.success() {
+            return Err(self.read_failure(domain, host, &output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn read_failure(&self, domain: &str, host: Host, stderr: &[u8]) -> ReadError {
+        let stderr = String::from_utf8_lossy(stderr);
+        if !stderr.contains("does not exist") {
+            return ReadError::BackendUnavailable(stderr.trim().to_string());
+        }
+        // `defaults` reports a missing key and a missing domain the same way.
+        if self.domain_exists(domain, host) {
+            ReadError::NotSet
+        } else {
+            ReadError::DomainMissing
+        }
+    }
+}
+
+/// The value arguments for `defaults write`: a type flag and the value, or a
+/// single XML fragment for arrays and dictionaries.
+pub fn write_args(value: &SettingValue) -> Vec<String> {
+    match value {
+        SettingValue::Float(v) => vec!["-float".to_string(), v.to_string()],
+        SettingValue::Bool(v) => vec![
+            "-bool".to_string(),
+            if *v { "TRUE" } else { "FALSE" }.to_string(),
+        ],
+        SettingValue::Int(v) => vec!["-int".to_string(), v.to_string()],
+        SettingValue::Str(v) => vec!["-string".to_string(), v.clone()],
+        SettingValue::Array(_) | SettingValue::Dict(_) => vec![xml_fragment(value)],
+    }
+}
+
+/// `defaults read` prints collections without element types, so read them
+/// from the domain's XML export instead.
+fn read_exported(
+    domain: &str,
+    host: Host,
+    key: &str,
+    value_type: ValueType,
+) -> Result<SettingValue, ReadError> {
+    let output = defaults_command(host)
+        .arg("export")
+        .arg(domain)
+        .arg("-")
+        .output()
+        .map_err(|e| ReadError::BackendUnavailable(format!("failed to execute defaults: {e}")))?;
+    if !output.status.success() {
+        return Err(ReadError::BackendUnavailable(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let dict = plist::Value::from_reader_xml(output.stdout.as_slice())
+        .map_err(|e| ReadError::ParseError(format!("defaults export output: {e}")))?
+        .into_dictionary()
+        .ok_or_else(|| ReadError::ParseError(format!("{domain} is not a dictionary")))?;
+    let value = dict.get(key).ok_or(ReadError::NotSet)?;
+    to_setting_value(value, value_type)
+}
+
+/// Domain name → key → value.
+pub type DomainStore = BTreeMap<String, BTreeMap<String, SettingValue>>;
+
+/// Everything a `MemoryBackend` holds. In JSON the any-host domains sit at
+/// the top level and current-host domains are nested under `"currentHost"`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrefsStore {
+    #[serde(
+        rename = "currentHost",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub current_host: DomainStore,
+    #[serde(flatten)]
+    pub any_host: DomainStore,
+}
+
+impl PrefsStore {
+    pub fn host(&self, host: Host) -> &DomainStore {
+        match host {
+            Host::Any => &self.any_host,
+            Host::Current => &self.current_host,
+        }
+    }
+
+    fn host_mut(&mut self, host: Host) -> &mut DomainStore {
+        match host {
+            Host::Any => &mut self.any_host,
+            Host::Current => &mut self.current_host,
+        }
+    }
+}
+
+/// Keeps preferences in memory, optionally persisted to a JSON file.
+/// Used for tests, demos and running on machines without `defaults`.
+#[derive(Default)]
+pub struct MemoryBackend {
+    store: Mutex<PrefsStore>,
+    path: Option<PathBuf>,
+}
+
+impl MemoryBackend {
+    /// Loads the store from `path` if it exists and writes every change back to it.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let store = load_store(&path)?;
+        Ok(Self {
+            store: Mutex::new(store),
+            path: Some(path),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn snapshot(&self) -> PrefsStore {
+        self.store.lock().unwrap().clone()
+    }
+
+    fn persist(&self, store: &PrefsStore) -> Result<()> {
+        if let Some(path) = &self.path {
+            let json = serde_json::to_string_pretty(store)?;
+            fs::write(path, json)
+                .context(format!("writing preferences file '{}'", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn load_store(path: &Path) -> Result<PrefsStore> {
+    if !path.exists() {
+        return Ok(PrefsStore::default());
+    }
+    let json = fs::read_to_string(path)
+        .context(format!("reading preferences file '{}'", path.display()))?;
+    serde_json::from_str(&json).context(format!("parsing preferences file '{}'", path.display()))
+}
+
+impl PreferencesBackend for MemoryBackend {
+    fn domain_exists(&self, domain: &str, host: Host) -> bool {
+        self.store.lock().unwrap().host(host).contains_key(domain)
+    }
+
+    fn read(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<SettingValue, ReadError> {
+        let store = self.store.lock().unwrap();
+        let value = store
+            .host(host)
+            .get(domain)
+            .ok_or(ReadError::DomainMissing)?
+            .get(key)
+            .ok_or(ReadError::NotSet)?;
+        // Convert the way the plist backend does so stored values are
+        // coerced identically.
+        to_setting_value(&to_plist(value), value_type)
+    }
+
+    fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+        let store = self.store.lock().unwrap();
+        let value = store
+            .host(host)
+            .get(domain)
+            .ok_or(ReadError::DomainMissing)?
+            .get(key)
+            .ok_or(ReadError::NotSet)?;
+        Ok(PlistType::expected(value.value_type()))
+    }
+
+    fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()> {
+        let mut store = self.store.lock().unwrap();
+        store
+            .host_mut(host)
+            .entry(domain.to_string())
+            .or_default()
+            .insert(key.to_string(), value.clone());
+        self.persist(&store)
+    }
+
+    fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(keys) = store.host_mut(host).get_mut(domain) {
+            keys.remove(key);
+        }
+        self.persist(&store)
+    }
+
+    /// Picks up edits other processes made to the backing file.
+    fn invalidate(&self) {
+        if let Some(path) = &self.path
+            && let Ok(store) = load_store(path)
+        {
+            *self.store.lock().unwrap() = store;
+        }
+    }
+
+    /// Every domain lives in the one backing file.
+    fn modified(&self, _domain: &str, _host: Host) -> Option<SystemTime> {
+        file_modified(self.path.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_round_trip() {
+        let backend = MemoryBackend::default();
+        assert!(!backend.domain_exists("NSGlobalDomain", Host::Any));
+
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Int(2),
+            )
+            .unwrap();
+        assert!(backend.domain_exists("NSGlobalDomain", Host::Any));
+        assert_eq!(
+            backend
+                .read("NSGlobalDomain", Host::Any, "KeyRepeat", ValueType::Int)
+                .unwrap(),
+            SettingValue::Int(2)
+        );
+        assert!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "InitialKeyRepeat",
+                    ValueType::Int
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn memory_reads_like_defaults_cli() {
+        let backend = MemoryBackend::default();
+        backend
+            .write(
+                "com.apple.AppleMultitouchTrackpad",
+                Host::Any,
+                "Clicking",
+                &SettingValue::Int(1),
+            )
+            .unwrap();
+        assert_eq!(
+            backend
+                .read(
+                    "com.apple.AppleMultitouchTrackpad",
+                    Host::Any,
+                    "Clicking",
+                    ValueType::Bool
+                )
+                .unwrap(),
+            SettingValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn hosts_are_separate() {
+        let backend = MemoryBackend::default();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Current,
+                "com.apple.mouse.tapBehavior",
+                &SettingValue::Int(1),
+            )
+            .unwrap();
+
+        assert!(!backend.domain_exists("NSGlobalDomain", Host::Any));
+        assert!(backend.domain_exists("NSGlobalDomain", Host::Current));
+        assert!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "com.apple.mouse.tapBehavior",
+                    ValueType::Int
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn file_backend_persists() {
+        let path = std::env::temp_dir().join(format!(
+            "myshenyatko-backend-test-{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let backend = MemoryBackend::open(&path).unwrap();
+        backend
+            .write(
+                "com.apple.universalaccess",
+                Host::Any,
+                "mouseDriverCursorSize",
+                &SettingValue::Float(1.5),
+            )
+            .unwrap();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Current,
+                "com.apple.mouse.tapBehavior",
+                &SettingValue::Int(1),
+            )
+            .unwrap();
+
+        let reopened = MemoryBackend::open(&path).unwrap();
+        assert_eq!(reopened.snapshot(), backend.snapshot());
+        assert_eq!(
+            reopened
+                .read(
+                    "com.apple.universalaccess",
+                    Host::Any,
+                    "mouseDriverCursorSize",
+                    ValueType::Float
+                )
+                .unwrap(),
+            SettingValue::Float(1.5)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+
+use super::backend::{PreferencesBackend, ReadError};
+use super::reader::read_setting;
+use super::{SettingDef, SettingValue, same_value};
+
+/// What to do with a change whose setting was modified by someone else
+/// after the change was planned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Leave the newer value alone and apply everything else
+    Skip,
+    /// Apply nothing
+    Fail,
+    /// Apply the change anyway
+    Overwrite,
+}
+
+/// A setting whose current value is no longer the one the change was based on.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub id: String,
+    pub description: String,
+    pub seen: Result<SettingValue, ReadError>,
+    pub current: Result<SettingValue, ReadError>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Result<SettingValue, ReadError>| match v {
+            Ok(v) => v.to_string(),
+            Err(e) => e.to_string(),
+        };
+        write!(
+            f,
+            "{} was {}, is now {}",
+            self.description,
+            show(&self.seen),
+            show(&self.current)
+        )
+    }
+}
+
+/// Re-reads each setting right before apply and returns the ones that no
+/// longer hold the value their change was based on.
+pub fn check_conflicts(
+    backend: &dyn PreferencesBackend,
+    bases: &[(&SettingDef, Result<SettingValue, ReadError>)],
+) -> Vec<Conflict> {
+    backend.invalidate();
+    bases
+        .iter()
+        .filter_map(|(def, seen)| check_conflict(backend, def, seen))
+        .collect()
+}
+
+/// Re-reads one setting and compares it with `seen`.
+pub fn check_conflict(
+    backend: &dyn PreferencesBackend,
+    def: &SettingDef,
+    seen: &Result<SettingValue, ReadError>,
+) -> Option<Conflict> {
+    let current = read_setting(backend, def);
+    (!same_reading(seen, &current)).then(|| Conflict {
+        id: def.id.to_string(),
+        description: def.description.to_string(),
+        seen: seen.clone(),
+        current,
+    })
+}
+
+/// Like `same_value`, but a key that couldn't be read only matches the
+/// same failure. Unset and missing domains count as the same.
+pub fn same_reading(
+    a: &Result<SettingValue, ReadError>,
+    b: &Result<SettingValue, ReadError>,
+) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => same_value(Some(a), Some(b)),
+        (Err(a), Err(b)) => a == b || (a.is_not_set() && b.is_not_set()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Host;
+    use crate::settings::backend::MemoryBackend;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn reports_values_changed_since_they_were_seen() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let repeat = defs.iter().find(|d| d.id == "key_repeat").unwrap();
+        let delay = defs.iter().find(|d| d.id == "initial_key_repeat").unwrap();
+        backend
+            .write(
+                &repeat.domain,
+                Host::Any,
+                &repeat.key,
+                &SettingValue::Int(2),
+            )
+            .unwrap();
+        backend
+            .write(&delay.domain, Host::Any, &delay.key, &SettingValue::Int(15))
+            .unwrap();
+
+        let conflicts = check_conflicts(
+            &backend,
+            &[
+                (repeat, Ok(SettingValue::Int(6))),
+                (delay, Ok(SettingValue::Int(15))),
+            ],
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].id, "key_repeat");
+        assert_eq!(conflicts[0].current, Ok(SettingValue::Int(2)));
+
+        // A value that couldn't be read isn't the same as an unset one.
+        backend
+            .write(
+                &repeat.domain,
+                Host::Any,
+                &repeat.key,
+                &SettingValue::Str("often".into()),
+            )
+            .unwrap();
+        let conflicts = check_conflicts(&backend, &[(repeat, Err(ReadError::NotSet))]);
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Key repeat rate (lower = faster) was not set, is now stored as string"
+        );
+    }
+}
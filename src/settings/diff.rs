@@ -0,0 +1,89 @@
+use super::SettingValue;
+
+/// One element that differs between two array or dictionary values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedChange {
+    /// Where the element sits, e.g. `[0].with`.
+    pub path: String,
+    /// `None` when the element was added.
+    pub old: Option<SettingValue>,
+    /// `None` when the element was removed.
+    pub new: Option<SettingValue>,
+}
+
+/// Walks two values in step and lists every leaf that was added, removed or
+/// changed. Arrays are compared index by index.
+pub fn nested_changes(old: &SettingValue, new: &SettingValue) -> Vec<NestedChange> {
+    let mut changes = Vec::new();
+    walk(String::new(), Some(old), Some(new), &mut changes);
+    changes
+}
+
+fn walk(
+    path: String,
+    old: Option<&SettingValue>,
+    new: Option<&SettingValue>,
+    changes: &mut Vec<NestedChange>,
+) {
+    match (old, new) {
+        (Some(SettingValue::Array(a)), Some(SettingValue::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                walk(format!("{path}[{i}]"), a.get(i), b.get(i), changes);
+            }
+        }
+        (Some(SettingValue::Dict(a)), Some(SettingValue::Dict(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                walk(child, a.get(key), b.get(key), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(NestedChange {
+            path,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn entry(replace: &str, with: &str) -> SettingValue {
+        SettingValue::Dict(BTreeMap::from([
+            (
+                "replace".to_string(),
+                SettingValue::Str(replace.to_string()),
+            ),
+            ("with".to_string(), SettingValue::Str(with.to_string())),
+        ]))
+    }
+
+    #[test]
+    fn reports_changed_added_and_removed_elements() {
+        let old = SettingValue::Array(vec![
+            entry("omw", "On my way!"),
+            entry("brb", "Be right back"),
+        ]);
+        let new = SettingValue::Array(vec![entry("omw", "On my way")]);
+
+        let changes = nested_changes(&old, &new);
+        let paths: Vec<_> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["[0].with", "[1]"]);
+        assert_eq!(
+            changes[0].new,
+            Some(SettingValue::Str("On my way".to_string()))
+        );
+        assert_eq!(changes[1].new, None);
+        assert!(nested_changes(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::fmt;
+
+use super::backend::{PreferencesBackend, ReadError};
+use super::plist_value::PlistType;
+use super::{Host, SettingDef, SettingValue};
+
+/// A key stored with a different plist type than its setting expects,
+/// usually because another tool wrote it with the wrong `defaults` flag.
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub id: String,
+    pub domain: String,
+    pub host: Host,
+    pub key: String,
+    pub expected: PlistType,
+    pub found: PlistType,
+    /// The stored value converted to the setting's type, when that's safe.
+    pub coerced: Result<SettingValue, ReadError>,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let host = if self.host == Host::Current {
+            " (currentHost)"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "{}.{}{host} is stored as {}, expected {}",
+            self.domain, self.key, self.found, self.expected
+        )
+    }
+}
+
+/// Checks the stored type of every location each setting is written to.
+pub fn find_drift(backend: &dyn PreferencesBackend, defs: &[SettingDef]) -> Vec<Drift> {
+    let mut drift = Vec::new();
+    for def in defs {
+        let expected = PlistType::expected(def.value_type);
+        for (domain, host) in def.locations() {
+            let Ok(found) = backend.stored_type(domain, host, &def.key) else {
+                continue;
+            };
+            if !found.stands_for(def.value_type) {
+                drift.push(Drift {
+                    id: def.id.to_string(),
+                    domain: domain.to_string(),
+                    host,
+                    key: def.key.to_string(),
+                    expected,
+                    found,
+                    coerced: backend.read(domain, host, &def.key, def.value_type),
+                });
+            }
+        }
+    }
+    drift
+}
+
+/// Rewrites a drifted key with its coerced value, which `write` stores with
+/// the right type flag.
+pub fn fix_drift(backend: &dyn PreferencesBackend, drift: &Drift) -> Result<SettingValue> {
+    let value = match &drift.coerced {
+        Ok(value) => value.clone(),
+        Err(e) => anyhow::bail!("can't convert the stored value ({e}); reset it instead"),
+    };
+    backend.write(&drift.domain, drift.host, &drift.key, &value)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::backend::MemoryBackend;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn finds_and_fixes_drifted_keys() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Str("2".to_string()),
+            )
+            .unwrap();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "com.apple.mouse.scaling",
+                &SettingValue::Str("fast".to_string()),
+            )
+            .unwrap();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "InitialKeyRepeat",
+                &SettingValue::Int(15),
+            )
+            .unwrap();
+        backend
+            .write(
+                "com.apple.AppleMultitouchTrackpad",
+                Host::Any,
+                "Clicking",
+                &SettingValue::Int(1),
+            )
+            .unwrap();
+
+        let mut drift = find_drift(&backend, &defs);
+        drift.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(drift.len(), 2);
+        assert_eq!(drift[0].key, "KeyRepeat");
+        assert_eq!(drift[0].found, PlistType::String);
+
+        assert_eq!(
+            fix_drift(&backend, &drift[0]).unwrap(),
+            SettingValue::Int(2)
+        );
+        assert!(fix_drift(&backend, &drift[1]).is_err());
+        assert_eq!(
+            backend.snapshot().any_host["NSGlobalDomain"]["KeyRepeat"],
+            SettingValue::Int(2)
+        );
+        assert_eq!(find_drift(&backend, &defs).len(), 1);
+    }
+}
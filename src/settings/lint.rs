@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::registry_file::{BadFile, RegistryFile};
+use super::{Constraint, SettingDef, SettingValue, ValueType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem with one setting definition.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// `built-in`, or the registry file the setting comes from.
+    pub source: String,
+    pub id: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.id.is_empty() {
+            return write!(f, "{severity}: {}: {}", self.source, self.message);
+        }
+        write!(
+            f,
+            "{severity}: {}: {}: {}",
+            self.source, self.id, self.message
+        )
+    }
+}
+
+impl Diagnostic {
+    /// A registry file that couldn't be read or parsed, so none of its
+    /// settings were checked.
+    pub fn unreadable(file: &BadFile) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            source: file.path.display().to_string(),
+            id: String::new(),
+            message: format!("{:#}", file.error),
+        }
+    }
+}
+
+/// Checks the built-in settings and registry files, including the id and
+/// domain/key clashes that `registry_file::merge` would reject.
+pub fn lint(built_in: &[SettingDef], files: &[RegistryFile]) -> Vec<Diagnostic> {
+    let sources = std::iter::once(("built-in".to_string(), built_in)).chain(
+        files
+            .iter()
+            .map(|file| (file.path.display().to_string(), file.defs.as_slice())),
+    );
+
+    let mut diagnostics = Vec::new();
+    // id → (source, def), with files replacing built-ins as when merging.
+    let mut merged: Vec<(String, &SettingDef)> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for (source, defs) in sources {
+        let mut report = |def: &SettingDef, severity, message: String| {
+            diagnostics.push(Diagnostic {
+                severity,
+                source: source.clone(),
+                id: def.id.to_string(),
+                message,
+            });
+        };
+        for def in defs {
+            for (severity, message) in check_def(def) {
+                report(def, severity, message);
+            }
+            match index.get(&*def.id) {
+                Some(&i) if merged[i].0 == source => {
+                    report(def, Severity::Error, "defined twice".to_string())
+                }
+                Some(&i) if merged[i].0 != "built-in" => {
+                    report(
+                        def,
+                        Severity::Error,
+                        format!("already defined in {}", merged[i].0),
+                    );
+                }
+                Some(&i) => merged[i] = (source.clone(), def),
+                None => {
+                    index.insert(&def.id, merged.len());
+                    merged.push((source.clone(), def));
+                }
+            }
+        }
+    }
+
+    let mut locations: HashMap<(&str, &str), &str> = HashMap::new();
+    for (source, def) in &merged {
+        if let Some(other) = locations.insert((&def.domain, &def.key), &def.id) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                source: source.clone(),
+                id: def.id.to_string(),
+                message: format!("{} {} is also used by '{other}'", def.domain, def.key),
+            });
+        }
+    }
+    diagnostics
+}
+
+fn check_def(def: &SettingDef) -> Vec<(Severity, String)> {
+    let mut problems = Vec::new();
+    let mut error = |message: String| problems.push((Severity::Error, message));
+
+    let expected = match &def.constraint {
+        Constraint::FloatRange(_) => Some(ValueType::Float),
+        Constraint::IntRange(_) => Some(ValueType::Int),
+        Constraint::StringOptions(_) => Some(ValueType::Str),
+        Constraint::Enum(_) if !matches!(def.value_type, ValueType::Int | ValueType::Str) => {
+            error(format!(
+                "named values on a {:?} setting; only Int and Str can have them",
+                def.value_type
+            ));
+            None
+        }
+        Constraint::Enum(_) => None,
+        Constraint::None => None,
+    };
+    if let Some(expected) = expected
+        && expected != def.value_type
+    {
+        error(format!(
+            "constraint expects {expected:?} values, but the type is {:?}",
+            def.value_type
+        ));
+    }
+
+    match &def.constraint {
+        Constraint::FloatRange(r) if r.step <= 0.0 => {
+            error(format!("step {} isn't positive", r.step))
+        }
+        Constraint::FloatRange(r) if r.min >= r.max => {
+            error(format!("range {}..{} is empty", r.min, r.max))
+        }
+        Constraint::FloatRange(r) => {
+            let steps = (r.max - r.min) / r.step;
+            if (steps - steps.round()).abs() > 1e-9 * steps.max(1.0) {
+                error(format!(
+                    "step {} doesn't divide the range {}..{}",
+                    r.step, r.min, r.max
+                ));
+            }
+        }
+        Constraint::IntRange(r) if r.min >= r.max => {
+            error(format!("range {}..{} is empty", r.min, r.max))
+        }
+        Constraint::StringOptions(options) if options.is_empty() => error("no options".to_string()),
+        Constraint::Enum(options) if options.is_empty() => error("no options".to_string()),
+        Constraint::Enum(options) => {
+            for (i, option) in options.iter().enumerate() {
+                if option.value.value_type() != def.value_type {
+                    error(format!(
+                        "option '{}' isn't a {:?}",
+                        option.label, def.value_type
+                    ));
+                } else if options[..i].iter().any(|o| o.value == option.value) {
+                    error(format!(
+                        "option '{}' repeats the value {}",
+                        option.label, option.value
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let fits = match (&def.factory_default, &def.constraint) {
+        (SettingValue::Float(v), Constraint::FloatRange(r)) => (r.min..=r.max).contains(v),
+        (SettingValue::Int(v), Constraint::IntRange(r)) => (r.min..=r.max).contains(v),
+        (SettingValue::Str(v), Constraint::StringOptions(options)) => {
+            options.iter().any(|o| o == v)
+        }
+        (value, Constraint::Enum(options)) => options.iter().any(|o| o.value == *value),
+        _ => true,
+    };
+    if def.factory_default.value_type() != def.value_type {
+        error(format!(
+            "default {:?} isn't a {:?}",
+            def.factory_default, def.value_type
+        ));
+    } else if !fits {
+        error(format!(
+            "default {} is outside the constraint",
+            def.factory_default
+        ));
+    }
+
+    if let Some(scale) = &def.scale {
+        if !scale.stops.is_sorted_by(|a, b| a.value < b.value) {
+            error("slider stops aren't in ascending order".to_string());
+        }
+        let outside = scale.stops.iter().find(|stop| match &def.constraint {
+            Constraint::FloatRange(r) => !(r.min..=r.max).contains(&stop.value),
+            Constraint::IntRange(r) => {
+                stop.value.fract() != 0.0 || !(r.min as f64..=r.max as f64).contains(&stop.value)
+            }
+            _ => false,
+        });
+        if let Some(stop) = outside {
+            error(format!(
+                "slider stop {} is outside the constraint",
+                stop.value
+            ));
+        }
+    }
+
+    if def.help.trim().is_empty() {
+        problems.push((Severity::Warning, "no help text".to_string()));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::registry::all_settings;
+    use crate::settings::registry_file::parse;
+    use std::path::PathBuf;
+
+    #[test]
+    fn built_in_registry_is_clean() {
+        let diagnostics = lint(&all_settings(), &[]);
+        assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    }
+
+    #[test]
+    fn reports_file_mistakes() {
+        let defs = parse(
+            r#"[
+                {"id": "key_repeat", "domain": "NSGlobalDomain", "key": "KeyRepeat", "type": "int",
+                 "default": 2, "description": "d", "group": "Keyboard", "help": "h"},
+                {"id": "spring", "domain": "com.apple.dock", "key": "spring", "type": "int",
+                 "constraint": {"floatRange": {"min": 0, "max": 1, "step": 0.3}},
+                 "default": 0, "description": "d", "group": "Mouse", "help": " "},
+                {"id": "style", "domain": "com.apple.dock", "key": "spring", "type": "str",
+                 "constraint": {"options": []}, "default": "x", "description": "d", "group": "Mouse", "help": "h"},
+                {"id": "style", "domain": "com.apple.dock", "key": "style", "type": "str",
+                 "default": "", "description": "d", "group": "Mouse", "help": "h"}
+            ]"#,
+        )
+        .unwrap();
+        let files = [RegistryFile {
+            path: PathBuf::from("extra.json"),
+            defs,
+        }];
+        let bad = BadFile {
+            path: PathBuf::from("bad.json"),
+            error: parse("[{}]").unwrap_err(),
+        };
+        assert_eq!(
+            Diagnostic::unreadable(&bad).to_string(),
+            "error: bad.json: missing field `id` at line 1 column 3"
+        );
+        let messages: Vec<_> = lint(&all_settings(), &files)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "error: extra.json: spring: constraint expects Float values, but the type is Int",
+                "error: extra.json: spring: step 0.3 doesn't divide the range 0..1",
+                "warning: extra.json: spring: no help text",
+                "error: extra.json: style: no options",
+                "error: extra.json: style: default x is outside the constraint",
+                "error: extra.json: style: defined twice",
+                "error: extra.json: style: com.apple.dock spring is also used by 'spring'",
+            ]
+        );
+    }
+}
@@ -1,8 +1,24 @@
+pub mod activation;
+pub mod apply;
+pub mod backend;
+pub mod conflict;
+pub mod diff;
+pub mod drift;
+pub mod lint;
+pub mod nix_options;
+pub mod plan;
+pub mod plist_backend;
+pub mod plist_value;
 pub mod reader;
 pub mod registry;
+pub mod registry_file;
+pub mod scale;
+pub mod verify;
 pub mod writer;
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -12,6 +28,40 @@ pub enum SettingValue {
     Bool(bool),
     Int(i64),
     Str(String),
+    Array(Vec<SettingValue>),
+    Dict(BTreeMap<String, SettingValue>),
+}
+
+impl SettingValue {
+    /// Equality that tolerates the rounding a float picks up on its way
+    /// through `defaults` and back.
+    pub fn approx_eq(&self, other: &SettingValue) -> bool {
+        match (self, other) {
+            (SettingValue::Float(a), SettingValue::Float(b)) => {
+                (a - b).abs() < 1e-6 * a.abs().max(1.0)
+            }
+            (SettingValue::Array(a), SettingValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.approx_eq(y))
+            }
+            (SettingValue::Dict(a), SettingValue::Dict(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, x)| b.get(k).is_some_and(|y| x.approx_eq(y)))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            SettingValue::Float(_) => ValueType::Float,
+            SettingValue::Bool(_) => ValueType::Bool,
+            SettingValue::Int(_) => ValueType::Int,
+            SettingValue::Str(_) => ValueType::Str,
+            SettingValue::Array(_) => ValueType::Array,
+            SettingValue::Dict(_) => ValueType::Dict,
+        }
+    }
 }
 
 impl fmt::Display for SettingValue {
@@ -21,19 +71,129 @@ impl fmt::Display for SettingValue {
             SettingValue::Bool(v) => write!(f, "{}", if *v { "On" } else { "Off" }),
             SettingValue::Int(v) => write!(f, "{v}"),
             SettingValue::Str(v) => write!(f, "{v}"),
+            SettingValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            SettingValue::Dict(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, item)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: {item}")?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Compares two possibly-unset values with `SettingValue::approx_eq`.
+pub fn same_value(a: Option<&SettingValue>, b: Option<&SettingValue>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.approx_eq(b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// A pending edit to one setting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingChange {
+    Set(SettingValue),
+    /// Delete the key everywhere so macOS falls back to its own default.
+    Reset,
+}
+
+impl fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingChange::Set(v) => write!(f, "{v}"),
+            SettingChange::Reset => write!(f, "system default"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ValueType {
     Float,
     Bool,
     Int,
     Str,
+    Array,
+    Dict,
+}
+
+/// Which copy of a domain a key lives in: the user's preferences, or the
+/// per-machine copy under `~/Library/Preferences/ByHost` that
+/// `defaults -currentHost` reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Host {
+    Any,
+    Current,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HostScope {
+    #[default]
+    AnyHost,
+    CurrentHost,
+    /// Written to both copies; the current-host value wins when reading.
+    Both,
+}
+
+impl HostScope {
+    /// Hosts in read precedence order. Writes go to all of them.
+    pub fn hosts(self) -> &'static [Host] {
+        match self {
+            HostScope::AnyHost => &[Host::Any],
+            HostScope::CurrentHost => &[Host::Current],
+            HostScope::Both => &[Host::Current, Host::Any],
+        }
+    }
+
+    pub fn is_any_host(&self) -> bool {
+        *self == HostScope::AnyHost
+    }
+}
+
+/// What has to happen after a write before macOS uses the new value.
+/// Ordered from least to most disruptive, which is also the order they run in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Activation {
+    /// Picked up as soon as the write reaches cfprefsd.
+    Immediate,
+    /// Read from the plist on disk, so cfprefsd has to flush its cache.
+    FlushPreferences,
+    /// Input device settings, reloaded by `activateSettings -u`.
+    ActivateSettings,
+    /// Only read when this process starts, e.g. `Dock` or `SystemUIServer`.
+    Restart(&'static str),
+    /// Only read at login.
+    Logout,
+}
+
+impl Activation {
+    pub fn label(self) -> Option<String> {
+        match self {
+            Activation::Immediate => None,
+            Activation::FlushPreferences => Some("flushes preferences".to_string()),
+            Activation::ActivateSettings => Some("reloads input settings".to_string()),
+            Activation::Restart(process) => Some(format!("restarts {process}")),
+            Activation::Logout => Some("logout required".to_string()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
 pub enum SettingGroup {
     Mouse,
     MouseHardware,
@@ -70,7 +230,13 @@ pub enum Tab {
 }
 
 impl Tab {
-    pub const ALL: [Tab; 5] = [Tab::Mouse, Tab::Trackpad, Tab::ScrollWindow, Tab::Cursor, Tab::Keyboard];
+    pub const ALL: [Tab; 5] = [
+        Tab::Mouse,
+        Tab::Trackpad,
+        Tab::ScrollWindow,
+        Tab::Cursor,
+        Tab::Keyboard,
+    ];
 
     pub fn label(self) -> &'static str {
         match self {
@@ -130,36 +296,75 @@ pub struct IntRange {
 pub enum Constraint {
     FloatRange(FloatRange),
     IntRange(IntRange),
-    StringOptions(Vec<&'static str>),
+    StringOptions(Vec<Text>),
+    /// Integer or string codes with a name each, in the order the TUI
+    /// cycles through them.
+    Enum(Vec<EnumOption>),
     None,
 }
 
+#[derive(Debug, Clone)]
+pub struct EnumOption {
+    pub value: SettingValue,
+    pub label: Text,
+}
+
+/// A string from the built-in registry, or one read from a registry file.
+pub type Text = Cow<'static, str>;
+
 #[derive(Debug, Clone)]
 pub struct SettingDef {
-    pub id: &'static str,
-    pub domain: &'static str,
-    pub key: &'static str,
+    pub id: Text,
+    pub domain: Text,
+    pub key: Text,
     pub value_type: ValueType,
     pub constraint: Constraint,
-    pub description: &'static str,
+    /// The value macOS uses when the key has never been written.
+    pub factory_default: SettingValue,
+    pub description: Text,
     pub group: SettingGroup,
-    pub mirror_domains: &'static [&'static str],
-    pub requires_logout: bool,
-    pub help: &'static str,
+    pub mirror_domains: Vec<Text>,
+    pub host_scope: HostScope,
+    pub activation: Activation,
+    pub help: Text,
+    pub scale: Option<scale::Scale>,
 }
 
 impl SettingDef {
+    /// Starting point for edits when the key isn't set on this machine.
     pub fn default_value(&self) -> SettingValue {
+        self.factory_default.clone()
+    }
+
+    /// Every domain/host pair the setting is written to, primary domain first.
+    pub fn locations(&self) -> Vec<(&str, Host)> {
+        std::iter::once(&self.domain)
+            .chain(&self.mirror_domains)
+            .flat_map(|domain| {
+                self.host_scope
+                    .hosts()
+                    .iter()
+                    .map(move |host| (domain.as_ref(), *host))
+            })
+            .collect()
+    }
+
+    /// The name of `value`, for settings with named values.
+    pub fn label(&self, value: &SettingValue) -> Option<&str> {
         match &self.constraint {
-            Constraint::FloatRange(FloatRange { min, .. }) => SettingValue::Float(*min),
-            Constraint::IntRange(IntRange { min, .. }) => SettingValue::Int(*min),
-            Constraint::StringOptions(opts) => SettingValue::Str(opts[0].to_string()),
-            Constraint::None => match self.value_type {
-                ValueType::Float => SettingValue::Float(0.0),
-                ValueType::Bool => SettingValue::Bool(false),
-                ValueType::Int => SettingValue::Int(0),
-                ValueType::Str => SettingValue::Str(String::new()),
-            },
+            Constraint::Enum(options) => options
+                .iter()
+                .find(|o| o.value == *value)
+                .map(|o| &*o.label),
+            _ => None,
         }
     }
+
+    pub fn requires_logout(&self) -> bool {
+        self.activation == Activation::Logout
+    }
+
+    pub fn is_factory(&self, value: &SettingValue) -> bool {
+        value.approx_eq(&self.factory_default)
+    }
 }
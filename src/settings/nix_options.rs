@@ -0,0 +1,142 @@
+/// Registry ids with a typed nix-darwin option under `system.defaults`, as
+/// attribute path segments. The option covers the setting's primary domain
+/// only. Settings whose options accept fewer values than the registry allows,
+/// such as `com.apple.mouse.tapBehavior`, are left out and exported through
+/// `CustomUserPreferences` instead.
+const NIX_DARWIN_OPTIONS: &[(&str, &[&str])] = &[
+    (
+        "mouse_tracking_speed",
+        &[".GlobalPreferences", "com.apple.mouse.scaling"],
+    ),
+    (
+        "natural_scroll",
+        &["NSGlobalDomain", "com.apple.swipescrolldirection"],
+    ),
+    (
+        "mouse_swipe_nav",
+        &["NSGlobalDomain", "AppleEnableMouseSwipeNavigateWithScrolls"],
+    ),
+    ("mouse_button_mode", &["magicmouse", "MouseButtonMode"]),
+    (
+        "trackpad_tracking_speed",
+        &["NSGlobalDomain", "com.apple.trackpad.scaling"],
+    ),
+    (
+        "trackpad_force_click",
+        &["NSGlobalDomain", "com.apple.trackpad.forceClick"],
+    ),
+    (
+        "trackpad_secondary_click",
+        &["NSGlobalDomain", "com.apple.trackpad.enableSecondaryClick"],
+    ),
+    (
+        "trackpad_swipe_nav",
+        &["NSGlobalDomain", "AppleEnableSwipeNavigateWithScrolls"],
+    ),
+    ("tp_clicking", &["trackpad", "Clicking"]),
+    ("tp_dragging", &["trackpad", "Dragging"]),
+    (
+        "tp_three_finger_drag",
+        &["trackpad", "TrackpadThreeFingerDrag"],
+    ),
+    ("tp_right_click", &["trackpad", "TrackpadRightClick"]),
+    (
+        "tp_first_click_threshold",
+        &["trackpad", "FirstClickThreshold"],
+    ),
+    (
+        "tp_second_click_threshold",
+        &["trackpad", "SecondClickThreshold"],
+    ),
+    ("tp_actuate_detents", &["trackpad", "ActuateDetents"]),
+    ("tp_force_suppressed", &["trackpad", "ForceSuppressed"]),
+    (
+        "show_scroll_bars",
+        &["NSGlobalDomain", "AppleShowScrollBars"],
+    ),
+    (
+        "scroller_paging",
+        &["NSGlobalDomain", "AppleScrollerPagingBehavior"],
+    ),
+    (
+        "smooth_scrolling",
+        &["NSGlobalDomain", "NSScrollAnimationEnabled"],
+    ),
+    (
+        "drag_on_gesture",
+        &["NSGlobalDomain", "NSWindowShouldDragOnGesture"],
+    ),
+    (
+        "springing_enabled",
+        &["NSGlobalDomain", "com.apple.springing.enabled"],
+    ),
+    (
+        "springing_delay",
+        &["NSGlobalDomain", "com.apple.springing.delay"],
+    ),
+    ("cursor_size", &["universalaccess", "mouseDriverCursorSize"]),
+    (
+        "zoom_scroll_toggle",
+        &["universalaccess", "closeViewScrollWheelToggle"],
+    ),
+    ("reduce_motion", &["universalaccess", "reduceMotion"]),
+    ("key_repeat", &["NSGlobalDomain", "KeyRepeat"]),
+    (
+        "initial_key_repeat",
+        &["NSGlobalDomain", "InitialKeyRepeat"],
+    ),
+    (
+        "press_and_hold",
+        &["NSGlobalDomain", "ApplePressAndHoldEnabled"],
+    ),
+    (
+        "fn_key_state",
+        &["NSGlobalDomain", "com.apple.keyboard.fnState"],
+    ),
+    (
+        "auto_correct",
+        &["NSGlobalDomain", "NSAutomaticSpellingCorrectionEnabled"],
+    ),
+    (
+        "auto_capitalize",
+        &["NSGlobalDomain", "NSAutomaticCapitalizationEnabled"],
+    ),
+    (
+        "smart_dashes",
+        &["NSGlobalDomain", "NSAutomaticDashSubstitutionEnabled"],
+    ),
+    (
+        "smart_quotes",
+        &["NSGlobalDomain", "NSAutomaticQuoteSubstitutionEnabled"],
+    ),
+    (
+        "period_substitution",
+        &["NSGlobalDomain", "NSAutomaticPeriodSubstitutionEnabled"],
+    ),
+];
+
+/// The nix-darwin option for a setting, e.g. `["NSGlobalDomain", "KeyRepeat"]`.
+pub fn nix_darwin_option(id: &str) -> Option<&'static [&'static str]> {
+    NIX_DARWIN_OPTIONS
+        .iter()
+        .find(|(i, _)| *i == id)
+        .map(|(_, path)| *path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn options_name_known_settings_and_keys() {
+        let defs = all_settings();
+        for (id, path) in NIX_DARWIN_OPTIONS {
+            let def = defs
+                .iter()
+                .find(|d| d.id == *id)
+                .unwrap_or_else(|| panic!("unknown id {id}"));
+            assert_eq!(path.last().copied(), Some(&*def.key), "{id}");
+        }
+    }
+}
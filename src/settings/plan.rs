@@ -0,0 +1,174 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+
+use super::activation::activation_commands;
+use super::backend::write_args;
+use super::writer::check_type;
+use super::{Host, SettingChange, SettingDef, SettingValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Write,
+    Delete,
+}
+
+/// One key an apply would write or delete, as a `defaults` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct Operation {
+    pub id: String,
+    pub action: Action,
+    pub domain: String,
+    #[serde(rename = "currentHost", skip_serializing_if = "std::ops::Not::not")]
+    pub current_host: bool,
+    pub key: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub value: Option<SettingValue>,
+    pub command: String,
+    /// `command` split into words.
+    #[serde(skip)]
+    pub args: Vec<String>,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.command)
+    }
+}
+
+/// Everything an apply would do, without doing it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    pub operations: Vec<Operation>,
+    /// Commands run afterwards so the settings take effect.
+    pub activations: Vec<String>,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for op in &self.operations {
+            writeln!(f, "{op}")?;
+        }
+        for command in &self.activations {
+            writeln!(f, "{command}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves changes into the writes and deletes `apply_changes` would make,
+/// every mirror domain and host included. Fails on the same type mismatches.
+pub fn plan_changes(changes: &[(&SettingDef, SettingChange)]) -> Result<Plan> {
+    let mut operations = Vec::new();
+    for (def, change) in changes {
+        if let SettingChange::Set(value) = change {
+            check_type(def.value_type, value)?;
+        }
+        for (domain, host) in def.locations() {
+            let mut args = vec!["defaults"];
+            if host == Host::Current {
+                args.push("-currentHost");
+            }
+            let (action, value, value_args) = match change {
+                SettingChange::Set(value) => {
+                    (Action::Write, Some(value.clone()), write_args(value))
+                }
+                SettingChange::Reset => (Action::Delete, None, Vec::new()),
+            };
+            args.extend([
+                if action == Action::Write {
+                    "write"
+                } else {
+                    "delete"
+                },
+                domain,
+                &def.key,
+            ]);
+            args.extend(value_args.iter().map(String::as_str));
+            operations.push(Operation {
+                id: def.id.to_string(),
+                action,
+                domain: domain.to_string(),
+                current_host: host == Host::Current,
+                key: def.key.to_string(),
+                value,
+                command: shell_line(&args),
+                args: args.iter().map(|a| a.to_string()).collect(),
+            });
+        }
+    }
+
+    let defs: Vec<_> = changes.iter().map(|(def, _)| *def).collect();
+    let activations = activation_commands(&defs)
+        .into_iter()
+        .map(|(program, args)| shell_line(&[&[program], args.as_slice()].concat()))
+        .collect();
+    Ok(Plan {
+        operations,
+        activations,
+    })
+}
+
+/// Joins arguments into a line a POSIX shell splits back into the same words.
+pub fn shell_line(args: &[&str]) -> String {
+    args.iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+=,".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn plans_mirrors_hosts_and_activation() {
+        let defs = all_settings();
+        let tap = defs.iter().find(|d| d.id == "tap_to_click").unwrap();
+        let repeat = defs.iter().find(|d| d.id == "key_repeat").unwrap();
+
+        let plan = plan_changes(&[
+            (tap, SettingChange::Set(SettingValue::Int(1))),
+            (repeat, SettingChange::Reset),
+        ])
+        .unwrap();
+
+        let commands: Vec<_> = plan
+            .operations
+            .iter()
+            .map(|op| op.command.as_str())
+            .collect();
+        assert_eq!(commands.len(), tap.locations().len() + 1);
+        assert!(commands.contains(
+            &"defaults -currentHost write NSGlobalDomain com.apple.mouse.tapBehavior -int 1"
+        ));
+        assert_eq!(
+            commands.last(),
+            Some(&"defaults delete NSGlobalDomain KeyRepeat")
+        );
+        assert_eq!(plan.activations.len(), 1);
+
+        assert!(
+            plan_changes(&[(repeat, SettingChange::Set(SettingValue::Str("2".into())))]).is_err()
+        );
+    }
+
+    #[test]
+    fn quotes_shell_arguments() {
+        assert_eq!(
+            shell_line(&["defaults", "write", "-string", "it's here", ""]),
+            r"defaults write -string 'it'\''s here' ''"
+        );
+    }
+}
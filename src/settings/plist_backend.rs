@@ -0,0 +1,449 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Instant, SystemTime};
+
+use super::backend::{
+    DefaultsCliBackend, DomainFetch, PreferencesBackend, ReadError, defaults_command, file_modified,
+};
+use super::plist_value::{PlistType, to_setting_value};
+use super::{Host, SettingValue, ValueType};
+
+/// Where `PlistBackend` gets a domain's plist from.
+enum DomainSource {
+    /// `<dir>/<domain>.plist`, e.g. `~/Library/Preferences`, and
+    /// `<dir>/ByHost/<domain>.<hardware uuid>.plist` for the current host.
+    Files(PathBuf),
+    /// `defaults [-currentHost] export <domain> -`, one process per domain.
+    DefaultsExport,
+}
+
+/// Reads preferences a whole domain plist at a time, parsing each domain once.
+/// Domains without a readable plist, and every write, go through the fallback
+/// backend.
+pub struct PlistBackend {
+    source: DomainSource,
+    fallback: Box<dyn PreferencesBackend>,
+    cache: Mutex<HashMap<(String, Host), Option<plist::Dictionary>>>,
+    written: Mutex<HashSet<(String, Host)>>,
+}
+
+impl PlistBackend {
+    pub fn new(prefs_dir: impl Into<PathBuf>, fallback: Box<dyn PreferencesBackend>) -> Self {
+        Self::with_source(DomainSource::Files(prefs_dir.into()), fallback)
+    }
+
+    pub fn for_current_user() -> Result<Self> {
+        let home = dirs::home_dir().context("could not determine home directory")?;
+        Ok(Self::new(
+            home.join("Library").join("Preferences"),
+            Box::new(DefaultsCliBackend),
+        ))
+    }
+
+    pub fn exported() -> Self {
+        Self::with_source(DomainSource::DefaultsExport, Box::new(DefaultsCliBackend))
+    }
+
+    fn with_source(source: DomainSource, fallback: Box<dyn PreferencesBackend>) -> Self {
+        Self {
+            source,
+            fallback,
+            cache: Mutex::new(HashMap::new()),
+            written: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn load(&self, domain: &str, host: Host) -> Option<plist::Dictionary> {
+        match &self.source {
+            DomainSource::Files(dir) => load_file(&plist_path(dir, domain, host)?),
+            DomainSource::DefaultsExport => load_export(domain, host),
+        }
+    }
+
+    /// Runs `f` against the parsed plist for `domain`, or returns `None` when
+    /// the plist can't be used and the caller should ask the fallback instead.
+    fn with_domain<T>(
+        &self,
+        domain: &str,
+        host: Host,
+        f: impl FnOnce(&plist::Dictionary) -> T,
+    ) -> Option<T> {
+        let entry = (domain.to_string(), host);
+        // cfprefsd flushes writes to disk lazily, so once we've written to a
+        // domain the file may be stale.
+        if self.written.lock().unwrap().contains(&entry) {
+            return None;
+        }
+        let mut cache = self.cache.lock().unwrap();
+        let dict = cache
+            .entry(entry)
+            .or_insert_with(|| self.load(domain, host));
+        dict.as_ref().map(f)
+    }
+}
+
+impl PreferencesBackend for PlistBackend {
+    fn domain_exists(&self, domain: &str, host: Host) -> bool {
+        self.with_domain(domain, host, |_| true)
+            .unwrap_or_else(|| self.fallback.domain_exists(domain, host))
+    }
+
+    fn read(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<SettingValue, ReadError> {
+        match self.with_domain(domain, host, |dict| dict.get(key).cloned()) {
+            Some(Some(value)) => to_setting_value(&value, value_type),
+            Some(None) => Err(ReadError::NotSet),
+            None => self.fallback.read(domain, host, key, value_type),
+        }
+    }
+
+    fn read_coerced(
+        &self,
+        domain: &str,
+        host: Host,
+        key: &str,
+        value_type: ValueType,
+    ) -> Result<(SettingValue, Option<PlistType>), ReadError> {
+        match self.with_domain(domain, host, |dict| dict.get(key).cloned()) {
+            Some(Some(value)) => {
+                let found = PlistType::of(&value);
+                let value = to_setting_value(&value, value_type)?;
+                Ok((value, (!found.stands_for(value_type)).then_some(found)))
+            }
+            Some(None) => Err(ReadError::NotSet),
+            None => self.fallback.read_coerced(domain, host, key, value_type),
+        }
+    }
+
+    fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+        match self.with_domain(domain, host, |dict| dict.get(key).map(PlistType::of)) {
+            Some(Some(found)) => Ok(found),
+            Some(None) => Err(ReadError::NotSet),
+            None => self.fallback.stored_type(domain, host, key),
+        }
+    }
+
+    fn write(&self, domain: &str, host: Host, key: &str, value: &SettingValue) -> Result<()> {
+        self.written
+            .lock()
+            .unwrap()
+            .insert((domain.to_string(), host));
+        self.fallback.write(domain, host, key, value)
+    }
+
+    fn delete(&self, domain: &str, host: Host, key: &str) -> Result<()> {
+        self.written
+            .lock()
+            .unwrap()
+            .insert((domain.to_string(), host));
+        self.fallback.delete(domain, host, key)
+    }
+
+    fn invalidate(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn modified(&self, domain: &str, host: Host) -> Option<SystemTime> {
+        match &self.source {
+            DomainSource::Files(dir) => file_modified(&plist_path(dir, domain, host)?),
+            DomainSource::DefaultsExport => None,
+        }
+    }
+
+    fn prefetch(&self, domains: &[(&str, Host)]) -> Vec<DomainFetch> {
+        let pending: Vec<(&str, Host)> = {
+            let cache = self.cache.lock().unwrap();
+            let mut seen = HashSet::new();
+            domains
+                .iter()
+                .copied()
+                .filter(|(d, h)| !cache.contains_key(&(d.to_string(), *h)) && seen.insert((*d, *h)))
+                .collect()
+        };
+
+        let fetched: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = pending
+                .iter()
+                .map(|&(domain, host)| {
+                    scope.spawn(move || {
+                        let start = Instant::now();
+                        let dict = self.load(domain, host);
+                        (domain.to_string(), host, dict, start.elapsed())
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let mut cache = self.cache.lock().unwrap();
+        fetched
+            .into_iter()
+            .map(|(domain, host, dict, elapsed)| {
+                let loaded = dict.is_some();
+                cache.insert((domain.clone(), host), dict);
+                DomainFetch {
+                    domain,
+                    host,
+                    elapsed,
+                    loaded,
+                }
+            })
+            .collect()
+    }
+}
+
+fn plist_path(dir: &Path, domain: &str, host: Host) -> Option<PathBuf> {
+    let file = match domain {
+        "NSGlobalDomain" | "-g" | "-globalDomain" => ".GlobalPreferences",
+        other => other,
+    };
+    match host {
+        Host::Any => Some(dir.join(format!("{file}.plist"))),
+        Host::Current => by_host_path(&dir.join("ByHost"), file),
+    }
+}
+
+/// ByHost plists are suffixed with the machine's hardware UUID. Only use one
+/// when it's unambiguous; with several (e.g. a migrated home directory) the
+/// fallback asks `defaults -currentHost`, which knows which one is ours.
+fn by_host_path(dir: &Path, file: &str) -> Option<PathBuf> {
+    let prefix = format!("{file}.");
+    let mut matches = std::fs::read_dir(dir).ok()?.filter_map(|entry| {
+        let path = entry.ok()?.path();
+        let name = path.file_name()?.to_str()?;
+        let host_id = name.strip_prefix(&prefix)?.strip_suffix(".plist")?;
+        (!host_id.is_empty() && !host_id.contains('.')).then_some(path)
+    });
+    let first = matches.next()?;
+    matches.next().is_none().then_some(first)
+}
+
+fn load_file(path: &Path) -> Option<plist::Dictionary> {
+    plist::Value::from_file(path).ok()?.into_dictionary()
+}
+
+fn load_export(domain: &str, host: Host) -> Option<plist::Dictionary> {
+    let output = defaults_command(host)
+        .arg("export")
+        .arg(domain)
+        .arg("-")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_dictionary(&output.stdout)
+}
+
+fn parse_dictionary(bytes: &[u8]) -> Option<plist::Dictionary> {
+    plist::Value::from_reader(Cursor::new(bytes))
+        .ok()?
+        .into_dictionary()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::backend::MemoryBackend;
+
+    fn fixture_backend(fallback: MemoryBackend) -> PlistBackend {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/preferences");
+        PlistBackend::new(dir, Box::new(fallback))
+    }
+
+    #[test]
+    fn reads_binary_global_domain() {
+        let backend = fixture_backend(MemoryBackend::default());
+        assert!(backend.domain_exists("NSGlobalDomain", Host::Any));
+        assert_eq!(
+            backend
+                .read("NSGlobalDomain", Host::Any, "KeyRepeat", ValueType::Int)
+                .unwrap(),
+            SettingValue::Int(2)
+        );
+        assert_eq!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "com.apple.mouse.scaling",
+                    ValueType::Float
+                )
+                .unwrap(),
+            SettingValue::Float(1.5)
+        );
+        assert_eq!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "com.apple.swipescrolldirection",
+                    ValueType::Bool
+                )
+                .unwrap(),
+            SettingValue::Bool(false)
+        );
+        assert_eq!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "AppleShowScrollBars",
+                    ValueType::Str
+                )
+                .unwrap(),
+            SettingValue::Str("Always".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_xml_domain() {
+        let backend = fixture_backend(MemoryBackend::default());
+        let domain = "com.apple.AppleMultitouchTrackpad";
+        assert_eq!(
+            backend
+                .read(domain, Host::Any, "Clicking", ValueType::Bool)
+                .unwrap(),
+            SettingValue::Bool(true)
+        );
+        assert_eq!(
+            backend
+                .read(
+                    domain,
+                    Host::Any,
+                    "TrackpadThreeFingerHorizSwipeGesture",
+                    ValueType::Int
+                )
+                .unwrap(),
+            SettingValue::Int(2)
+        );
+        assert!(
+            backend
+                .read(domain, Host::Any, "Dragging", ValueType::Bool)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reads_by_host_domain() {
+        let backend = fixture_backend(MemoryBackend::default());
+        assert_eq!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Current,
+                    "com.apple.mouse.tapBehavior",
+                    ValueType::Int
+                )
+                .unwrap(),
+            SettingValue::Int(1)
+        );
+        assert!(
+            backend
+                .read(
+                    "NSGlobalDomain",
+                    Host::Any,
+                    "com.apple.mouse.tapBehavior",
+                    ValueType::Int
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn falls_back_for_missing_plists_and_after_writes() {
+        let fallback = MemoryBackend::default();
+        fallback
+            .write(
+                "com.apple.universalaccess",
+                Host::Any,
+                "reduceMotion",
+                &SettingValue::Bool(true),
+            )
+            .unwrap();
+        let backend = fixture_backend(fallback);
+
+        assert!(backend.domain_exists("com.apple.universalaccess", Host::Any));
+        assert_eq!(
+            backend
+                .read(
+                    "com.apple.universalaccess",
+                    Host::Any,
+                    "reduceMotion",
+                    ValueType::Bool
+                )
+                .unwrap(),
+            SettingValue::Bool(true)
+        );
+
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Int(6),
+            )
+            .unwrap();
+        assert_eq!(
+            backend
+                .read("NSGlobalDomain", Host::Any, "KeyRepeat", ValueType::Int)
+                .unwrap(),
+            SettingValue::Int(6)
+        );
+    }
+
+    #[test]
+    fn prefetch_loads_each_domain_once() {
+        let backend = fixture_backend(MemoryBackend::default());
+        let fetched = backend.prefetch(&[
+            ("NSGlobalDomain", Host::Any),
+            ("com.apple.AppleMultitouchTrackpad", Host::Any),
+            ("NSGlobalDomain", Host::Any),
+            ("NSGlobalDomain", Host::Current),
+            ("com.apple.universalaccess", Host::Any),
+        ]);
+
+        let mut loaded: Vec<_> = fetched
+            .iter()
+            .map(|f| (f.domain.as_str(), f.host, f.loaded))
+            .collect();
+        loaded.sort();
+        assert_eq!(
+            loaded,
+            vec![
+                ("NSGlobalDomain", Host::Any, true),
+                ("NSGlobalDomain", Host::Current, true),
+                ("com.apple.AppleMultitouchTrackpad", Host::Any, true),
+                ("com.apple.universalaccess", Host::Any, false),
+            ]
+        );
+        assert!(
+            backend
+                .prefetch(&[("NSGlobalDomain", Host::Any)])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn parses_exported_xml() {
+        let xml = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/preferences/com.apple.AppleMultitouchTrackpad.plist"),
+        )
+        .unwrap();
+        let dict = parse_dictionary(&xml).unwrap();
+        assert_eq!(
+            dict.get("FirstClickThreshold"),
+            Some(&plist::Value::Integer(1.into()))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::backend::ReadError;
+use super::reader::parse_value;
+use super::{SettingValue, ValueType};
+
+/// Converts a stored plist value to the type a setting expects. Scalars go
+/// through the text `defaults read` prints so every backend coerces them the
+/// same way; arrays and dictionaries keep the types of their elements.
+pub fn to_setting_value(
+    value: &plist::Value,
+    value_type: ValueType,
+) -> Result<SettingValue, ReadError> {
+    let mismatch = || ReadError::TypeMismatch {
+        found: PlistType::of(value),
+    };
+    match (value, value_type) {
+        (plist::Value::Array(_), ValueType::Array)
+        | (plist::Value::Dictionary(_), ValueType::Dict) => Ok(untyped(value)),
+        (plist::Value::Array(_) | plist::Value::Dictionary(_), _)
+        | (_, ValueType::Array | ValueType::Dict) => Err(mismatch()),
+        (scalar, _) => parse_value(&defaults_repr(scalar), value_type).map_err(|_| mismatch()),
+    }
+}
+
+/// The type a value is stored as in a preferences plist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlistType {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Array,
+    Dictionary,
+    Data,
+    Date,
+}
+
+impl PlistType {
+    pub fn of(value: &plist::Value) -> Self {
+        match value {
+            plist::Value::Array(_) => PlistType::Array,
+            plist::Value::Dictionary(_) => PlistType::Dictionary,
+            plist::Value::Boolean(_) => PlistType::Boolean,
+            plist::Value::Data(_) => PlistType::Data,
+            plist::Value::Date(_) => PlistType::Date,
+            plist::Value::Real(_) => PlistType::Float,
+            plist::Value::Integer(_) => PlistType::Integer,
+            _ => PlistType::String,
+        }
+    }
+
+    /// How a setting of this type is stored when written correctly.
+    pub fn expected(value_type: ValueType) -> Self {
+        match value_type {
+            ValueType::Float => PlistType::Float,
+            ValueType::Bool => PlistType::Boolean,
+            ValueType::Int => PlistType::Integer,
+            ValueType::Str => PlistType::String,
+            ValueType::Array => PlistType::Array,
+            ValueType::Dict => PlistType::Dictionary,
+        }
+    }
+
+    /// Whether a key stored as `self` is a correct way to store a setting of
+    /// `value_type`. macOS writes some booleans as 0/1 integers itself.
+    pub fn stands_for(self, value_type: ValueType) -> bool {
+        self == Self::expected(value_type)
+            || (self == PlistType::Integer && value_type == ValueType::Bool)
+    }
+
+    /// Parses the type name `defaults read-type` prints.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "boolean" => PlistType::Boolean,
+            "integer" => PlistType::Integer,
+            "float" => PlistType::Float,
+            "string" => PlistType::String,
+            "array" => PlistType::Array,
+            "dictionary" => PlistType::Dictionary,
+            "data" => PlistType::Data,
+            "date" => PlistType::Date,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for PlistType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PlistType::Boolean => "boolean",
+            PlistType::Integer => "integer",
+            PlistType::Float => "float",
+            PlistType::String => "string",
+            PlistType::Array => "array",
+            PlistType::Dictionary => "dictionary",
+            PlistType::Data => "data",
+            PlistType::Date => "date",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Converts a plist value using the types it was stored with.
+fn untyped(value: &plist::Value) -> SettingValue {
+    match value {
+        plist::Value::Array(items) => SettingValue::Array(items.iter().map(untyped).collect()),
+        plist::Value::Dictionary(dict) => SettingValue::Dict(
+            dict.iter()
+                .map(|(k, v)| (k.clone(), untyped(v)))
+                .collect::<BTreeMap<_, _>>(),
+        ),
+        plist::Value::Boolean(v) => SettingValue::Bool(*v),
+        plist::Value::Integer(v) => match v.as_signed() {
+            Some(i) => SettingValue::Int(i),
+            None => SettingValue::Str(v.to_string()),
+        },
+        plist::Value::Real(v) => SettingValue::Float(*v),
+        other => SettingValue::Str(defaults_repr(other)),
+    }
+}
+
+pub fn to_plist(value: &SettingValue) -> plist::Value {
+    match value {
+        SettingValue::Float(v) => plist::Value::Real(*v),
+        SettingValue::Bool(v) => plist::Value::Boolean(*v),
+        SettingValue::Int(v) => plist::Value::Integer((*v).into()),
+        SettingValue::Str(v) => plist::Value::String(v.clone()),
+        SettingValue::Array(items) => plist::Value::Array(items.iter().map(to_plist).collect()),
+        SettingValue::Dict(entries) => plist::Value::Dictionary(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), to_plist(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Renders a plist scalar the way `defaults read` prints it.
+fn defaults_repr(value: &plist::Value) -> String {
+    match value {
+        plist::Value::Boolean(v) => if *v { "1" } else { "0" }.to_string(),
+        plist::Value::Integer(v) => v.to_string(),
+        plist::Value::Real(v) => v.to_string(),
+        plist::Value::String(v) => v.clone(),
+        plist::Value::Date(v) => v.to_xml_format(),
+        plist::Value::Data(v) => format!("{{length = {}}}", v.len()),
+        other => format!("{other:?}"),
+    }
+}
+
+/// The XML form `defaults write <domain> <key> '<value>'` accepts for
+/// arrays and dictionaries.
+pub fn xml_fragment(value: &SettingValue) -> String {
+    let mut out = String::new();
+    write_xml(value, &mut out);
+    out
+}
+
+fn write_xml(value: &SettingValue, out: &mut String) {
+    match value {
+        SettingValue::Float(v) => out.push_str(&format!("<real>{v}</real>")),
+        SettingValue::Bool(v) => out.push_str(if *v { "<true/>" } else { "<false/>" }),
+        SettingValue::Int(v) => out.push_str(&format!("<integer>{v}</integer>")),
+        SettingValue::Str(v) => out.push_str(&format!("<string>{}</string>", escape_xml(v))),
+        SettingValue::Array(items) => {
+            out.push_str("<array>");
+            for item in items {
+                write_xml(item, out);
+            }
+            out.push_str("</array>");
+        }
+        SettingValue::Dict(entries) => {
+            out.push_str("<dict>");
+            for (key, item) in entries {
+                out.push_str(&format!("<key>{}</key>", escape_xml(key)));
+                write_xml(item, out);
+            }
+            out.push_str("</dict>");
+        }
+    }
+}
+
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacements() -> SettingValue {
+        SettingValue::Array(vec![SettingValue::Dict(BTreeMap::from([
+            ("on".to_string(), SettingValue::Int(1)),
+            ("replace".to_string(), SettingValue::Str("omw".to_string())),
+            (
+                "with".to_string(),
+                SettingValue::Str("On my way & <soon>".to_string()),
+            ),
+        ]))])
+    }
+
+    #[test]
+    fn collections_round_trip() {
+        let value = replacements();
+        assert_eq!(
+            to_setting_value(&to_plist(&value), ValueType::Array).unwrap(),
+            value
+        );
+        assert_eq!(
+            to_setting_value(&to_plist(&value), ValueType::Dict),
+            Err(ReadError::TypeMismatch {
+                found: PlistType::Array
+            })
+        );
+        assert!(to_setting_value(&plist::Value::Integer(1.into()), ValueType::Array).is_err());
+    }
+
+    #[test]
+    fn type_names_round_trip() {
+        for t in [
+            PlistType::Boolean,
+            PlistType::Float,
+            PlistType::Dictionary,
+            PlistType::Date,
+        ] {
+            assert_eq!(PlistType::from_name(&t.to_string()), Some(t));
+        }
+    }
+
+    #[test]
+    fn xml_fragment_parses_as_plist() {
+        let xml = format!(
+            "<plist version=\"1.0\">{}</plist>",
+            xml_fragment(&replacements())
+        );
+        let parsed = plist::Value::from_reader_xml(xml.as_bytes()).unwrap();
+        assert_eq!(
+            to_setting_value(&parsed, ValueType::Array).unwrap(),
+            replacements()
+        );
+    }
+}
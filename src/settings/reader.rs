@@ -1,26 +1,81 @@
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
-use std::process::Command;
 
-use super::{SettingDef, SettingValue, ValueType};
+use super::backend::{DomainFetch, PreferencesBackend, ReadError};
+use super::plist_value::PlistType;
+use super::{Host, SettingDef, SettingValue, ValueType};
 
-pub fn read_all(settings: &[SettingDef]) -> HashMap<String, SettingValue> {
-    let mut values = HashMap::new();
+/// Loads every domain used by `settings` in one go, for backends that
+/// support it, so the per-key lookups that follow stay in memory.
+pub fn prefetch(backend: &dyn PreferencesBackend, settings: &[SettingDef]) -> Vec<DomainFetch> {
+    backend.prefetch(&domains(settings))
+}
+
+/// Every domain and host `settings` are read from, without repeats.
+pub fn domains(settings: &[SettingDef]) -> Vec<(&str, Host)> {
+    let mut domains: Vec<(&str, Host)> = settings
+        .iter()
+        .flat_map(|d| d.host_scope.hosts().iter().map(|host| (&*d.domain, *host)))
+        .collect();
+    domains.sort();
+    domains.dedup();
+    domains
+}
+
+/// Every setting's current value, or why it couldn't be read.
+#[derive(Debug, Clone, Default)]
+pub struct Readings {
+    pub values: HashMap<String, SettingValue>,
+    /// Includes settings that simply aren't set; see `ReadError::is_not_set`.
+    pub errors: HashMap<String, ReadError>,
+    /// Settings whose value was coerced from a different stored type.
+    pub drifted: HashMap<String, PlistType>,
+}
+
+impl Readings {
+    /// Replaces what's known about `defs` with `newer`, a read of just those.
+    pub fn replace(&mut self, defs: &[SettingDef], newer: Readings) {
+        for def in defs {
+            self.values.remove(&*def.id);
+            self.errors.remove(&*def.id);
+            self.drifted.remove(&*def.id);
+        }
+        self.values.extend(newer.values);
+        self.errors.extend(newer.errors);
+        self.drifted.extend(newer.drifted);
+    }
+}
+
+pub fn read_all(backend: &dyn PreferencesBackend, settings: &[SettingDef]) -> Readings {
+    let mut readings = Readings::default();
     for def in settings {
-        if let Ok(val) = read_setting(def) {
-            values.insert(def.id.to_string(), val);
+        match read_located(backend, def) {
+            Ok((val, drifted)) => {
+                if let Some(found) = drifted {
+                    readings.drifted.insert(def.id.to_string(), found);
+                }
+                readings.values.insert(def.id.to_string(), val);
+            }
+            Err(e) => {
+                readings.errors.insert(def.id.to_string(), e);
+            }
         }
     }
-    values
+    readings
 }
 
-pub fn available_setting_ids(settings: &[SettingDef]) -> HashSet<String> {
-    let mut domain_cache: HashMap<&str, bool> = HashMap::new();
+pub fn available_setting_ids(
+    backend: &dyn PreferencesBackend,
+    settings: &[SettingDef],
+) -> HashSet<String> {
+    let mut domain_cache: HashMap<(&str, Host), bool> = HashMap::new();
     let mut available = HashSet::new();
     for def in settings {
-        let exists = *domain_cache
-            .entry(def.domain)
-            .or_insert_with(|| domain_exists(def.domain));
+        let exists = def.host_scope.hosts().iter().any(|host| {
+            *domain_cache
+                .entry((&*def.domain, *host))
+                .or_insert_with(|| backend.domain_exists(&def.domain, *host))
+        });
         if exists {
             available.insert(def.id.to_string());
         }
@@ -28,44 +83,63 @@ pub fn available_setting_ids(settings: &[SettingDef]) -> HashSet<String> {
     available
 }
 
-fn domain_exists(domain: &str) -> bool {
-    Command::new("defaults")
-        .arg("read")
-        .arg(domain)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// Reads the primary domain, trying each host in the setting's scope in
+/// precedence order. If none has a value, a real failure is reported over
+/// the key simply being absent.
+pub fn read_setting(
+    backend: &dyn PreferencesBackend,
+    def: &SettingDef,
+) -> Result<SettingValue, ReadError> {
+    read_located(backend, def).map(|(value, _)| value)
 }
 
-pub fn read_setting(def: &SettingDef) -> Result<SettingValue> {
-    let output = Command::new("defaults")
-        .arg("read")
-        .arg(def.domain)
-        .arg(def.key)
-        .output()
-        .context("failed to execute defaults command")?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "defaults read failed for {}.{}: {}",
-            def.domain,
-            def.key,
-            String::from_utf8_lossy(&output.stderr).trim()
-        );
+/// Like `read_setting`, also returning the stored type if the value had to
+/// be coerced from one that doesn't stand for the setting's type.
+fn read_located(
+    backend: &dyn PreferencesBackend,
+    def: &SettingDef,
+) -> Result<(SettingValue, Option<PlistType>), ReadError> {
+    let mut errors = Vec::new();
+    for host in def.host_scope.hosts() {
+        match backend.read_coerced(&def.domain, *host, &def.key, def.value_type) {
+            Ok(read) => return Ok(read),
+            Err(e) => errors.push(e),
+        }
     }
+    errors.sort_by_key(|e| match e {
+        ReadError::DomainMissing => 2,
+        ReadError::NotSet => 1,
+        _ => 0,
+    });
+    Err(errors.remove(0))
+}
 
-    let raw = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    parse_value(&raw, def.value_type)
+/// Whether `raw` is how `defaults read` prints a value stored with the
+/// expected type (or one that stands for it). Other spellings, like `YES` for a
+/// bool, mean the value was coerced. A number stored as a string prints the same
+/// as the number, so that drift only shows up in `find_drift`.
+pub(super) fn prints_as(raw: &str, value_type: ValueType) -> bool {
+    let trimmed = raw.trim();
+    match value_type {
+        ValueType::Float => trimmed.parse::<f64>().is_ok(),
+        ValueType::Bool => matches!(trimmed, "0" | "1"),
+        ValueType::Int => trimmed.parse::<i64>().is_ok(),
+        ValueType::Str | ValueType::Array | ValueType::Dict => true,
+    }
 }
 
-fn parse_value(raw: &str, value_type: ValueType) -> Result<SettingValue> {
+/// Parses the text `defaults read` prints. Values stored with another type
+/// are coerced when nothing is lost, e.g. `"2.0"` or `2.0` for an int and
+/// `"YES"` for a bool.
+pub(super) fn parse_value(raw: &str, value_type: ValueType) -> Result<SettingValue> {
+    let trimmed = raw.trim();
     match value_type {
         ValueType::Float => {
-            let v: f64 = raw.parse().context("parsing float")?;
+            let v: f64 = trimmed.parse().context("parsing float")?;
             Ok(SettingValue::Float(v))
         }
         ValueType::Bool => {
-            let v = match raw {
+            let v = match trimmed.to_ascii_lowercase().as_str() {
                 "1" | "true" | "yes" => true,
                 "0" | "false" | "no" => false,
                 _ => anyhow::bail!("unexpected bool value: {raw}"),
@@ -73,10 +147,21 @@ fn parse_value(raw: &str, value_type: ValueType) -> Result<SettingValue> {
             Ok(SettingValue::Bool(v))
         }
         ValueType::Int => {
-            let v: i64 = raw.parse().context("parsing int")?;
-            Ok(SettingValue::Int(v))
+            if let Ok(v) = trimmed.parse::<i64>() {
+                return Ok(SettingValue::Int(v));
+            }
+            let v: f64 = trimmed.parse().context("parsing int")?;
+            if v.fract() != 0.0 || v.abs() > i64::MAX as f64 {
+                anyhow::bail!("{raw} is not a whole number");
+            }
+            Ok(SettingValue::Int(v as i64))
         }
         ValueType::Str => Ok(SettingValue::Str(raw.to_string())),
+        // `defaults read` prints collections as old-style plists, which don't
+        // say whether an element is a string or a number.
+        ValueType::Array | ValueType::Dict => {
+            anyhow::bail!("{value_type:?} values can't be parsed from text")
+        }
     }
 }
 
@@ -116,6 +201,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_coerces_safely() {
+        assert_eq!(
+            parse_value("2.0", ValueType::Int).unwrap(),
+            SettingValue::Int(2)
+        );
+        assert!(parse_value("2.5", ValueType::Int).is_err());
+        assert_eq!(
+            parse_value(" YES", ValueType::Bool).unwrap(),
+            SettingValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn marks_only_coerced_values() {
+        use crate::settings::backend::MemoryBackend;
+        use crate::settings::registry::all_settings;
+
+        assert!(prints_as("1", ValueType::Bool));
+        assert!(!prints_as("YES", ValueType::Bool));
+        assert!(!prints_as("2.0", ValueType::Int));
+
+        let backend = MemoryBackend::default();
+        let defs: Vec<_> = all_settings()
+            .into_iter()
+            .filter(|d| d.id == "tp_clicking" || d.id == "key_repeat")
+            .collect();
+        backend
+            .write(
+                "com.apple.AppleMultitouchTrackpad",
+                Host::Any,
+                "Clicking",
+                &SettingValue::Int(1),
+            )
+            .unwrap();
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Str("2".to_string()),
+            )
+            .unwrap();
+        let readings = read_all(&backend, &defs);
+        assert_eq!(readings.values["tp_clicking"], SettingValue::Bool(true));
+        assert_eq!(readings.drifted.keys().collect::<Vec<_>>(), ["key_repeat"]);
+    }
+
     #[test]
     fn parse_string() {
         assert_eq!(
@@ -123,4 +256,56 @@ mod tests {
             SettingValue::Str("WhenScrolling".to_string())
         );
     }
+
+    #[test]
+    fn current_host_wins_for_both_scope() {
+        use crate::settings::backend::MemoryBackend;
+        use crate::settings::registry::all_settings;
+
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "tap_to_click").unwrap();
+
+        backend
+            .write(&def.domain, Host::Any, &def.key, &SettingValue::Int(0))
+            .unwrap();
+        assert_eq!(read_setting(&backend, def).unwrap(), SettingValue::Int(0));
+
+        backend
+            .write(&def.domain, Host::Current, &def.key, &SettingValue::Int(1))
+            .unwrap();
+        assert_eq!(read_setting(&backend, def).unwrap(), SettingValue::Int(1));
+    }
+
+    #[test]
+    fn read_errors_prefer_real_failures() {
+        use crate::settings::backend::MemoryBackend;
+        use crate::settings::registry::all_settings;
+
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "tap_to_click").unwrap();
+        assert_eq!(read_setting(&backend, def), Err(ReadError::DomainMissing));
+
+        backend
+            .write(&def.domain, Host::Current, "other", &SettingValue::Int(0))
+            .unwrap();
+        assert_eq!(read_setting(&backend, def), Err(ReadError::NotSet));
+
+        backend
+            .write(
+                &def.domain,
+                Host::Any,
+                &def.key,
+                &SettingValue::Str("often".to_string()),
+            )
+            .unwrap();
+        let readings = read_all(&backend, std::slice::from_ref(def));
+        assert_eq!(
+            readings.errors[&*def.id],
+            ReadError::TypeMismatch {
+                found: PlistType::String
+            }
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use super::scale::{Scale, Stop};
 use super::*;
 
 const NSGLOBAL: &str = "NSGlobalDomain";
@@ -7,6 +8,20 @@ const MULTITOUCH_TRACKPAD: &str = "com.apple.AppleMultitouchTrackpad";
 const BT_TRACKPAD: &str = "com.apple.driver.AppleBluetoothMultitouch.trackpad";
 const UNIVERSAL_ACCESS: &str = "com.apple.universalaccess";
 
+/// The tracking speed slider in System Settings, for mice and trackpads.
+const TRACKING_STOPS: &[(f64, &str)] = &[
+    (0.0, "Slow"),
+    (0.125, ""),
+    (0.5, ""),
+    (0.6875, ""),
+    (0.875, ""),
+    (1.0, ""),
+    (1.5, ""),
+    (2.0, ""),
+    (2.5, ""),
+    (3.0, "Fast"),
+];
+
 fn float_range(min: f64, max: f64, step: f64) -> Constraint {
     Constraint::FloatRange(FloatRange { min, max, step })
 }
@@ -15,819 +30,1158 @@ fn int_range(min: i64, max: i64) -> Constraint {
     Constraint::IntRange(IntRange { min, max })
 }
 
+fn scale(factor: f64, unit: &'static str, stops: &[(f64, &'static str)]) -> Option<Scale> {
+    let stops = stops
+        .iter()
+        .map(|&(value, label)| Stop {
+            value,
+            label: (!label.is_empty()).then(|| label.into()),
+        })
+        .collect();
+    Some(Scale {
+        factor,
+        unit: unit.into(),
+        stops,
+    })
+}
+
+fn int_enum(options: &[(i64, &'static str)]) -> Constraint {
+    let options = options.iter().map(|&(value, label)| EnumOption {
+        value: SettingValue::Int(value),
+        label: label.into(),
+    });
+    Constraint::Enum(options.collect())
+}
+
+fn string_enum(options: &[(&'static str, &'static str)]) -> Constraint {
+    let options = options.iter().map(|&(value, label)| EnumOption {
+        value: SettingValue::Str(value.to_string()),
+        label: label.into(),
+    });
+    Constraint::Enum(options.collect())
+}
+
+/// The built-in settings merged with those from `registry.d`. Files that
+/// don't parse are skipped with a warning so the rest of the tool still works.
+pub fn load() -> anyhow::Result<Vec<SettingDef>> {
+    let mut defs = all_settings();
+    if let Some(dir) = registry_file::registry_dir() {
+        let read = registry_file::read_dir(&dir)?;
+        for bad in &read.bad {
+            eprintln!(
+                "Skipping {}: {:#}; run `myshenyatko registry lint` for details",
+                bad.path.display(),
+                bad.error
+            );
+        }
+        registry_file::merge(&mut defs, read.files)?;
+    }
+    Ok(defs)
+}
+
 pub fn all_settings() -> Vec<SettingDef> {
     vec![
         // ── Group 1: Mouse (NSGlobalDomain) ──
         SettingDef {
-            id: "mouse_tracking_speed",
-            domain: NSGLOBAL,
-            key: "com.apple.mouse.scaling",
+            id: "mouse_tracking_speed".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.mouse.scaling".into(),
             value_type: ValueType::Float,
             constraint: float_range(-1.0, 11.0, 0.25),
-            description: "Tracking speed",
+            factory_default: SettingValue::Float(0.875),
+            description: "Tracking speed".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "How fast the cursor moves. Higher = faster",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "How fast the cursor moves. Higher = faster".into(),
+            scale: scale(1.0, "", TRACKING_STOPS),
         },
         SettingDef {
-            id: "mouse_linear",
-            domain: NSGLOBAL,
-            key: "com.apple.mouse.linear",
+            id: "mouse_linear".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.mouse.linear".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Disable acceleration",
+            factory_default: SettingValue::Bool(false),
+            description: "Disable acceleration".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Uses constant speed instead of acceleration curve",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Uses constant speed instead of acceleration curve".into(),
+            scale: None,
         },
         SettingDef {
-            id: "scroll_wheel_speed",
-            domain: NSGLOBAL,
-            key: "com.apple.scrollwheel.scaling",
+            id: "scroll_wheel_speed".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.scrollwheel.scaling".into(),
             value_type: ValueType::Float,
             constraint: float_range(-1.0, 11.0, 0.25),
-            description: "Scroll wheel speed",
+            factory_default: SettingValue::Float(0.3125),
+            description: "Scroll wheel speed".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "How fast the scroll wheel scrolls. Higher = faster",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "How fast the scroll wheel scrolls. Higher = faster".into(),
+            scale: None,
         },
         SettingDef {
-            id: "natural_scroll",
-            domain: NSGLOBAL,
-            key: "com.apple.swipescrolldirection",
+            id: "natural_scroll".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.swipescrolldirection".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Natural scroll direction",
+            factory_default: SettingValue::Bool(true),
+            description: "Natural scroll direction".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: true,
-            help: "Content moves with finger direction, like a touchscreen",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Content moves with finger direction, like a touchscreen".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_swipe_nav",
-            domain: NSGLOBAL,
-            key: "AppleEnableMouseSwipeNavigateWithScrolls",
+            id: "mouse_swipe_nav".into(),
+            domain: NSGLOBAL.into(),
+            key: "AppleEnableMouseSwipeNavigateWithScrolls".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Swipe navigation with scrolls",
+            factory_default: SettingValue::Bool(true),
+            description: "Swipe navigation with scrolls".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Swipe left/right to go back/forward in apps",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Swipe left/right to go back/forward in apps".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_double_click_threshold",
-            domain: NSGLOBAL,
-            key: "com.apple.mouse.doubleClickThreshold",
+            id: "mouse_double_click_threshold".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.mouse.doubleClickThreshold".into(),
             value_type: ValueType::Float,
             constraint: float_range(0.0, 3.0, 0.1),
-            description: "Double-click threshold (seconds)",
+            factory_default: SettingValue::Float(0.5),
+            description: "Double-click threshold (seconds)".into(),
             group: SettingGroup::Mouse,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Max time between clicks for a double-click. Higher = more forgiving",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Max time between clicks for a double-click. Higher = more forgiving".into(),
+            scale: scale(1.0, "s", &[]),
         },
         // ── Group 2: Mouse Hardware ──
         SettingDef {
-            id: "mouse_button_mode",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseButtonMode",
+            id: "mouse_button_mode".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseButtonMode".into(),
             value_type: ValueType::Str,
-            constraint: Constraint::StringOptions(vec!["OneButton", "TwoButton"]),
-            description: "Button mode",
+            constraint: string_enum(&[
+                ("OneButton", "Primary click only"),
+                ("TwoButton", "Secondary click"),
+            ]),
+            factory_default: SettingValue::Str("OneButton".into()),
+            description: "Button mode".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "OneButton = single click, TwoButton = left/right click",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Whether the right side of the mouse does a secondary click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_button_division",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseButtonDivision",
+            id: "mouse_button_division".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseButtonDivision".into(),
             value_type: ValueType::Int,
             constraint: int_range(0, 100),
-            description: "Button division (L/R split)",
+            factory_default: SettingValue::Int(55),
+            description: "Button division (L/R split)".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "Where left/right click zones split. Higher = more right-click area",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Where left/right click zones split. Higher = more right-click area".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_horizontal_scroll",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseHorizontalScroll",
+            id: "mouse_horizontal_scroll".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseHorizontalScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Horizontal scroll",
+            factory_default: SettingValue::Bool(true),
+            description: "Horizontal scroll".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "Allow side-to-side scrolling",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Allow side-to-side scrolling".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_vertical_scroll",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseVerticalScroll",
+            id: "mouse_vertical_scroll".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseVerticalScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Vertical scroll",
+            factory_default: SettingValue::Bool(true),
+            description: "Vertical scroll".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "Allow up/down scrolling",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Allow up/down scrolling".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_momentum_scroll",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseMomentumScroll",
+            id: "mouse_momentum_scroll".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseMomentumScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Momentum scroll",
+            factory_default: SettingValue::Bool(true),
+            description: "Momentum scroll".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "Scroll continues after lifting finger",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Scroll continues after lifting finger".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_one_finger_double_tap",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseOneFingerDoubleTapGesture",
+            id: "mouse_one_finger_double_tap".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseOneFingerDoubleTapGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "One-finger double-tap",
+            constraint: int_enum(&[(0, "Off"), (1, "Smart zoom")]),
+            factory_default: SettingValue::Int(1),
+            description: "One-finger double-tap".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "0=off, 1=smart zoom",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Double-tap with one finger to zoom in on a page".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_two_finger_double_tap",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseTwoFingerDoubleTapGesture",
+            id: "mouse_two_finger_double_tap".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseTwoFingerDoubleTapGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Two-finger double-tap (smart zoom)",
+            constraint: int_enum(&[(0, "Off"), (1, "Smart zoom")]),
+            factory_default: SettingValue::Int(0),
+            description: "Two-finger double-tap (smart zoom)".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "0=off, 1=smart zoom",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "Double-tap with two fingers to zoom in on a page".into(),
+            scale: None,
         },
         SettingDef {
-            id: "mouse_two_finger_swipe",
-            domain: MULTITOUCH_MOUSE,
-            key: "MouseTwoFingerHorizSwipeGesture",
+            id: "mouse_two_finger_swipe".into(),
+            domain: MULTITOUCH_MOUSE.into(),
+            key: "MouseTwoFingerHorizSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Two-finger swipe between pages",
+            constraint: int_enum(&[
+                (0, "Off"),
+                (1, "Swipe between pages"),
+                (2, "Swipe between full-screen apps"),
+            ]),
+            factory_default: SettingValue::Int(2),
+            description: "Two-finger swipe between pages".into(),
             group: SettingGroup::MouseHardware,
-            mirror_domains: &[BT_MOUSE],
-            requires_logout: false,
-            help: "0=off, 1=swipe pages, 2=swipe full-screen apps",
+            mirror_domains: vec![BT_MOUSE.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "What swiping left or right with two fingers does".into(),
+            scale: None,
         },
         // ── Group 3: Trackpad (NSGlobalDomain) ──
         SettingDef {
-            id: "trackpad_tracking_speed",
-            domain: NSGLOBAL,
-            key: "com.apple.trackpad.scaling",
+            id: "trackpad_tracking_speed".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.trackpad.scaling".into(),
             value_type: ValueType::Float,
             constraint: float_range(-1.0, 11.0, 0.25),
-            description: "Tracking speed",
+            factory_default: SettingValue::Float(0.6875),
+            description: "Tracking speed".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "How fast the cursor moves. Higher = faster",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "How fast the cursor moves. Higher = faster".into(),
+            scale: scale(1.0, "", TRACKING_STOPS),
         },
         SettingDef {
-            id: "trackpad_force_click",
-            domain: NSGLOBAL,
-            key: "com.apple.trackpad.forceClick",
+            id: "trackpad_force_click".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.trackpad.forceClick".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Force click & haptic feedback",
+            factory_default: SettingValue::Bool(true),
+            description: "Force click & haptic feedback".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Hard press triggers Force Click and haptic response",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Hard press triggers Force Click and haptic response".into(),
+            scale: None,
         },
         SettingDef {
-            id: "trackpad_secondary_click",
-            domain: NSGLOBAL,
-            key: "com.apple.trackpad.enableSecondaryClick",
+            id: "trackpad_secondary_click".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.trackpad.enableSecondaryClick".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Secondary click",
+            factory_default: SettingValue::Bool(true),
+            description: "Secondary click".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Two-finger click acts as right-click",
+            mirror_domains: vec![],
+            host_scope: HostScope::Both,
+            activation: Activation::ActivateSettings,
+            help: "Two-finger click acts as right-click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "trackpad_corner_click",
-            domain: NSGLOBAL,
-            key: "com.apple.trackpad.trackpadCornerClickBehavior",
+            id: "trackpad_corner_click".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.trackpad.trackpadCornerClickBehavior".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Corner click behavior",
+            constraint: int_enum(&[(0, "Off"), (1, "Secondary click")]),
+            factory_default: SettingValue::Int(0),
+            description: "Corner click behavior".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "0=off, 1=secondary click",
+            mirror_domains: vec![],
+            host_scope: HostScope::Both,
+            activation: Activation::ActivateSettings,
+            help: "What clicking in a bottom corner does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tap_to_click",
-            domain: NSGLOBAL,
-            key: "com.apple.mouse.tapBehavior",
+            id: "tap_to_click".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.mouse.tapBehavior".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Tap to click",
+            constraint: int_enum(&[(0, "Off"), (1, "On")]),
+            factory_default: SettingValue::Int(0),
+            description: "Tap to click".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: true,
-            help: "0=off, 1=tap to click",
+            mirror_domains: vec![],
+            host_scope: HostScope::Both,
+            activation: Activation::ActivateSettings,
+            help: "Tap with one finger instead of pressing to click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "trackpad_swipe_nav",
-            domain: NSGLOBAL,
-            key: "AppleEnableSwipeNavigateWithScrolls",
+            id: "trackpad_swipe_nav".into(),
+            domain: NSGLOBAL.into(),
+            key: "AppleEnableSwipeNavigateWithScrolls".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Swipe navigation with scrolls",
+            factory_default: SettingValue::Bool(true),
+            description: "Swipe navigation with scrolls".into(),
             group: SettingGroup::Trackpad,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Swipe left/right to go back/forward in apps",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Swipe left/right to go back/forward in apps".into(),
+            scale: None,
         },
         // ── Group 4: Trackpad Hardware ──
         SettingDef {
-            id: "tp_clicking",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "Clicking",
+            id: "tp_clicking".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "Clicking".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Tap to click",
+            factory_default: SettingValue::Bool(false),
+            description: "Tap to click".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Light tap registers as a click",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Light tap registers as a click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_dragging",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "Dragging",
+            id: "tp_dragging".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "Dragging".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Tap and drag",
+            factory_default: SettingValue::Bool(false),
+            description: "Tap and drag".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Double-tap and hold to drag items",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Double-tap and hold to drag items".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_drag_lock",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "DragLock",
+            id: "tp_drag_lock".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "DragLock".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Drag lock",
+            factory_default: SettingValue::Bool(false),
+            description: "Drag lock".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Drag doesn't end when finger lifts briefly",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Drag doesn't end when finger lifts briefly".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_three_finger_drag",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadThreeFingerDrag",
+            id: "tp_three_finger_drag".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadThreeFingerDrag".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Three-finger drag",
+            factory_default: SettingValue::Bool(false),
+            description: "Three-finger drag".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Use three fingers to drag items",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Use three fingers to drag items".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_right_click",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadRightClick",
+            id: "tp_right_click".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadRightClick".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Two-finger right-click",
+            factory_default: SettingValue::Bool(true),
+            description: "Two-finger right-click".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Two-finger click acts as right-click",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Two-finger click acts as right-click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_corner_secondary",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadCornerSecondaryClick",
+            id: "tp_corner_secondary".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadCornerSecondaryClick".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Corner secondary click",
+            constraint: int_enum(&[
+                (0, "Off"),
+                (1, "Bottom-left corner"),
+                (2, "Bottom-right corner"),
+            ]),
+            factory_default: SettingValue::Int(0),
+            description: "Corner secondary click".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 1=bottom-left corner, 2=bottom-right corner",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Which corner clicks as a secondary click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_scroll",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadScroll",
+            id: "tp_scroll".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Vertical scroll",
+            factory_default: SettingValue::Bool(true),
+            description: "Vertical scroll".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Two-finger swipe scrolls vertically",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Two-finger swipe scrolls vertically".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_horiz_scroll",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadHorizScroll",
+            id: "tp_horiz_scroll".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadHorizScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Horizontal scroll",
+            factory_default: SettingValue::Bool(true),
+            description: "Horizontal scroll".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Two-finger swipe scrolls horizontally",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Two-finger swipe scrolls horizontally".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_momentum_scroll",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadMomentumScroll",
+            id: "tp_momentum_scroll".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadMomentumScroll".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Momentum scrolling",
+            factory_default: SettingValue::Bool(true),
+            description: "Momentum scrolling".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Scroll continues after lifting fingers",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Scroll continues after lifting fingers".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_pinch",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadPinch",
+            id: "tp_pinch".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadPinch".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Pinch to zoom",
+            factory_default: SettingValue::Bool(true),
+            description: "Pinch to zoom".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Pinch two fingers to zoom in/out",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Pinch two fingers to zoom in/out".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_rotate",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadRotate",
+            id: "tp_rotate".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadRotate".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Two-finger rotate",
+            factory_default: SettingValue::Bool(true),
+            description: "Two-finger rotate".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Rotate two fingers to rotate content",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Rotate two fingers to rotate content".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_three_finger_tap",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadThreeFingerTapGesture",
+            id: "tp_three_finger_tap".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadThreeFingerTapGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Three-finger tap action",
+            constraint: int_enum(&[(0, "Off"), (2, "Look up & data detectors")]),
+            factory_default: SettingValue::Int(0),
+            description: "Three-finger tap action".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=look up & data detectors",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "What tapping with three fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_two_finger_double_tap",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadTwoFingerDoubleTapGesture",
+            id: "tp_two_finger_double_tap".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadTwoFingerDoubleTapGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Two-finger double-tap",
+            constraint: int_enum(&[(0, "Off"), (1, "Smart zoom")]),
+            factory_default: SettingValue::Int(1),
+            description: "Two-finger double-tap".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 1=smart zoom",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Double-tap with two fingers to zoom in on a page".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_right_edge_swipe",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadTwoFingerFromRightEdgeSwipeGesture",
+            id: "tp_right_edge_swipe".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadTwoFingerFromRightEdgeSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 3),
-            description: "Right-edge swipe",
+            constraint: int_enum(&[(0, "Off"), (3, "Notification Center")]),
+            factory_default: SettingValue::Int(3),
+            description: "Right-edge swipe".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 3=Notification Center",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What swiping left from the right edge with two fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_three_finger_horiz_swipe",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadThreeFingerHorizSwipeGesture",
+            id: "tp_three_finger_horiz_swipe".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadThreeFingerHorizSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Three-finger horizontal swipe",
+            constraint: int_enum(&[(0, "Off"), (1, "Switch pages"), (2, "Switch spaces")]),
+            factory_default: SettingValue::Int(2),
+            description: "Three-finger horizontal swipe".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 1=switch pages, 2=switch spaces",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "What swiping left or right with three fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_three_finger_vert_swipe",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadThreeFingerVertSwipeGesture",
+            id: "tp_three_finger_vert_swipe".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadThreeFingerVertSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Three-finger vertical swipe",
+            constraint: int_enum(&[(0, "Off"), (2, "Mission Control / App Exposé")]),
+            factory_default: SettingValue::Int(2),
+            description: "Three-finger vertical swipe".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=Mission Control / App Expose",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What swiping up or down with three fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_four_finger_horiz_swipe",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadFourFingerHorizSwipeGesture",
+            id: "tp_four_finger_horiz_swipe".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadFourFingerHorizSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Four-finger horizontal swipe",
+            constraint: int_enum(&[(0, "Off"), (2, "Switch spaces")]),
+            factory_default: SettingValue::Int(2),
+            description: "Four-finger horizontal swipe".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=switch spaces",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What swiping left or right with four fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_four_finger_vert_swipe",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadFourFingerVertSwipeGesture",
+            id: "tp_four_finger_vert_swipe".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadFourFingerVertSwipeGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Four-finger vertical swipe",
+            constraint: int_enum(&[(0, "Off"), (2, "Mission Control / App Exposé")]),
+            factory_default: SettingValue::Int(2),
+            description: "Four-finger vertical swipe".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=Mission Control / App Expose",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What swiping up or down with four fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_four_finger_pinch",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadFourFingerPinchGesture",
+            id: "tp_four_finger_pinch".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadFourFingerPinchGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Four-finger pinch",
+            constraint: int_enum(&[(0, "Off"), (2, "Launchpad")]),
+            factory_default: SettingValue::Int(2),
+            description: "Four-finger pinch".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=Launchpad",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What pinching with thumb and three fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_five_finger_pinch",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "TrackpadFiveFingerPinchGesture",
+            id: "tp_five_finger_pinch".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "TrackpadFiveFingerPinchGesture".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Five-finger pinch",
+            constraint: int_enum(&[(0, "Off"), (2, "Launchpad")]),
+            factory_default: SettingValue::Int(2),
+            description: "Five-finger pinch".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=off, 2=Launchpad",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Restart("Dock"),
+            help: "What pinching with thumb and four fingers does".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_first_click_threshold",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "FirstClickThreshold",
+            id: "tp_first_click_threshold".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "FirstClickThreshold".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "First click pressure",
+            constraint: int_enum(&[(0, "Light"), (1, "Medium"), (2, "Firm")]),
+            factory_default: SettingValue::Int(1),
+            description: "First click pressure".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=light, 1=medium, 2=firm",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "How hard you press to click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_second_click_threshold",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "SecondClickThreshold",
+            id: "tp_second_click_threshold".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "SecondClickThreshold".into(),
             value_type: ValueType::Int,
-            constraint: int_range(0, 2),
-            description: "Second click pressure",
+            constraint: int_enum(&[(0, "Light"), (1, "Medium"), (2, "Firm")]),
+            factory_default: SettingValue::Int(1),
+            description: "Second click pressure".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "0=light, 1=medium, 2=firm",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "How hard you press for a Force click".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_actuate_detents",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "ActuateDetents",
+            id: "tp_actuate_detents".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "ActuateDetents".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Haptic feedback",
+            factory_default: SettingValue::Bool(true),
+            description: "Haptic feedback".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Vibration feedback on trackpad clicks",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Vibration feedback on trackpad clicks".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_force_suppressed",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "ForceSuppressed",
+            id: "tp_force_suppressed".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "ForceSuppressed".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Suppress force click",
+            factory_default: SettingValue::Bool(false),
+            description: "Suppress force click".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Disables force click entirely",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Disables force click entirely".into(),
+            scale: None,
         },
         SettingDef {
-            id: "tp_usb_mouse_stops",
-            domain: MULTITOUCH_TRACKPAD,
-            key: "USBMouseStopsTrackpad",
+            id: "tp_usb_mouse_stops".into(),
+            domain: MULTITOUCH_TRACKPAD.into(),
+            key: "USBMouseStopsTrackpad".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Disable when USB mouse connected",
+            factory_default: SettingValue::Bool(false),
+            description: "Disable when USB mouse connected".into(),
             group: SettingGroup::TrackpadHardware,
-            mirror_domains: &[BT_TRACKPAD],
-            requires_logout: false,
-            help: "Trackpad turns off when a USB mouse is plugged in",
+            mirror_domains: vec![BT_TRACKPAD.into()],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "Trackpad turns off when a USB mouse is plugged in".into(),
+            scale: None,
         },
         // ── Group 5: Scroll & Window Behavior ──
         SettingDef {
-            id: "show_scroll_bars",
-            domain: NSGLOBAL,
-            key: "AppleShowScrollBars",
+            id: "show_scroll_bars".into(),
+            domain: NSGLOBAL.into(),
+            key: "AppleShowScrollBars".into(),
             value_type: ValueType::Str,
-            constraint: Constraint::StringOptions(vec!["WhenScrolling", "Automatic", "Always"]),
-            description: "Scrollbar visibility",
+            constraint: string_enum(&[
+                ("WhenScrolling", "When scrolling"),
+                ("Automatic", "Automatically"),
+                ("Always", "Always"),
+            ]),
+            factory_default: SettingValue::Str("Automatic".into()),
+            description: "Scrollbar visibility".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "When scrollbars appear in windows",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "When scrollbars appear in windows".into(),
+            scale: None,
         },
         SettingDef {
-            id: "scroller_paging",
-            domain: NSGLOBAL,
-            key: "AppleScrollerPagingBehavior",
+            id: "scroller_paging".into(),
+            domain: NSGLOBAL.into(),
+            key: "AppleScrollerPagingBehavior".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Click scrollbar to jump to spot",
+            factory_default: SettingValue::Bool(false),
+            description: "Click scrollbar to jump to spot".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "On: jump to clicked spot. Off: scroll one page",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "On: jump to clicked spot. Off: scroll one page".into(),
+            scale: None,
         },
         SettingDef {
-            id: "smooth_scrolling",
-            domain: NSGLOBAL,
-            key: "NSScrollAnimationEnabled",
+            id: "smooth_scrolling".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSScrollAnimationEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Smooth scrolling",
+            factory_default: SettingValue::Bool(true),
+            description: "Smooth scrolling".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Animate scrolling instead of jumping",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Animate scrolling instead of jumping".into(),
+            scale: None,
         },
         SettingDef {
-            id: "double_click_titlebar",
-            domain: NSGLOBAL,
-            key: "AppleMiniaturizeOnDoubleClick",
+            id: "double_click_titlebar".into(),
+            domain: NSGLOBAL.into(),
+            key: "AppleMiniaturizeOnDoubleClick".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Double-click title bar to minimize",
+            factory_default: SettingValue::Bool(false),
+            description: "Double-click title bar to minimize".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Double-click window title bar to minimize it",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Double-click window title bar to minimize it".into(),
+            scale: None,
         },
         SettingDef {
-            id: "drag_on_gesture",
-            domain: NSGLOBAL,
-            key: "NSWindowShouldDragOnGesture",
+            id: "drag_on_gesture".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSWindowShouldDragOnGesture".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Ctrl+Cmd drag window anywhere",
+            factory_default: SettingValue::Bool(false),
+            description: "Ctrl+Cmd drag window anywhere".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Hold Ctrl+Cmd and drag anywhere in a window to move it",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Hold Ctrl+Cmd and drag anywhere in a window to move it".into(),
+            scale: None,
         },
         SettingDef {
-            id: "springing_enabled",
-            domain: NSGLOBAL,
-            key: "com.apple.springing.enabled",
+            id: "springing_enabled".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.springing.enabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Spring-loaded folders",
+            factory_default: SettingValue::Bool(true),
+            description: "Spring-loaded folders".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Hovering over a folder while dragging opens it",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Hovering over a folder while dragging opens it".into(),
+            scale: None,
         },
         SettingDef {
-            id: "springing_delay",
-            domain: NSGLOBAL,
-            key: "com.apple.springing.delay",
+            id: "springing_delay".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.springing.delay".into(),
             value_type: ValueType::Float,
             constraint: float_range(0.0, 3.0, 0.1),
-            description: "Spring-load delay",
+            factory_default: SettingValue::Float(0.5),
+            description: "Spring-load delay".into(),
             group: SettingGroup::ScrollWindow,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "How long to hover before folder opens. Higher = slower",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "How long to hover before folder opens. Higher = slower".into(),
+            scale: scale(1.0, "s", &[]),
         },
         // ── Group 6: Cursor & Accessibility ──
         SettingDef {
-            id: "cursor_size",
-            domain: UNIVERSAL_ACCESS,
-            key: "mouseDriverCursorSize",
+            id: "cursor_size".into(),
+            domain: UNIVERSAL_ACCESS.into(),
+            key: "mouseDriverCursorSize".into(),
             value_type: ValueType::Float,
             constraint: float_range(1.0, 10.0, 0.25),
-            description: "Cursor size",
+            factory_default: SettingValue::Float(1.0),
+            description: "Cursor size".into(),
             group: SettingGroup::CursorAccessibility,
-            mirror_domains: &[],
-            requires_logout: true,
-            help: "Size of the mouse cursor. Higher = bigger",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Logout,
+            help: "Size of the mouse cursor. Higher = bigger".into(),
+            scale: None,
         },
         SettingDef {
-            id: "zoom_scroll_toggle",
-            domain: UNIVERSAL_ACCESS,
-            key: "closeViewScrollWheelToggle",
+            id: "zoom_scroll_toggle".into(),
+            domain: UNIVERSAL_ACCESS.into(),
+            key: "closeViewScrollWheelToggle".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Scroll-wheel zoom",
+            factory_default: SettingValue::Bool(false),
+            description: "Scroll-wheel zoom".into(),
             group: SettingGroup::CursorAccessibility,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Hold modifier key + scroll to zoom the screen",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::FlushPreferences,
+            help: "Hold modifier key + scroll to zoom the screen".into(),
+            scale: None,
         },
         SettingDef {
-            id: "zoom_modifier_key",
-            domain: UNIVERSAL_ACCESS,
-            key: "closeViewScrollWheelModifiersInt",
+            id: "zoom_modifier_key".into(),
+            domain: UNIVERSAL_ACCESS.into(),
+            key: "closeViewScrollWheelModifiersInt".into(),
             value_type: ValueType::Int,
             constraint: int_range(0, 1572864),
-            description: "Zoom modifier key bitmask",
+            factory_default: SettingValue::Int(262144),
+            description: "Zoom modifier key bitmask".into(),
             group: SettingGroup::CursorAccessibility,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Which modifier key activates scroll zoom (macOS key bitmask)",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::FlushPreferences,
+            help: "Which modifier key activates scroll zoom (macOS key bitmask)".into(),
+            scale: None,
         },
         SettingDef {
-            id: "reduce_motion",
-            domain: UNIVERSAL_ACCESS,
-            key: "reduceMotion",
+            id: "reduce_motion".into(),
+            domain: UNIVERSAL_ACCESS.into(),
+            key: "reduceMotion".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Reduce motion",
+            factory_default: SettingValue::Bool(false),
+            description: "Reduce motion".into(),
             group: SettingGroup::CursorAccessibility,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Minimizes animations across macOS",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::FlushPreferences,
+            help: "Minimizes animations across macOS".into(),
+            scale: None,
         },
         SettingDef {
-            id: "shake_to_locate",
-            domain: UNIVERSAL_ACCESS,
-            key: "ShakeMouseCursorToLocate",
+            id: "shake_to_locate".into(),
+            domain: UNIVERSAL_ACCESS.into(),
+            key: "ShakeMouseCursorToLocate".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Shake cursor to locate",
+            factory_default: SettingValue::Bool(true),
+            description: "Shake cursor to locate".into(),
             group: SettingGroup::CursorAccessibility,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Shake mouse rapidly to enlarge cursor temporarily",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::FlushPreferences,
+            help: "Shake mouse rapidly to enlarge cursor temporarily".into(),
+            scale: None,
         },
         // ── Group 7: Keyboard ──
         SettingDef {
-            id: "key_repeat",
-            domain: NSGLOBAL,
-            key: "KeyRepeat",
+            id: "key_repeat".into(),
+            domain: NSGLOBAL.into(),
+            key: "KeyRepeat".into(),
             value_type: ValueType::Int,
             constraint: int_range(0, 120),
-            description: "Key repeat rate (lower = faster)",
+            factory_default: SettingValue::Int(6),
+            description: "Key repeat rate (lower = faster)".into(),
             group: SettingGroup::Keyboard,
-            mirror_domains: &[],
-            requires_logout: true,
-            help: "Interval between repeated keystrokes. Lower = faster",
-        },
-        SettingDef {
-            id: "initial_key_repeat",
-            domain: NSGLOBAL,
-            key: "InitialKeyRepeat",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Logout,
+            help: "Interval between repeated keystrokes. Lower = faster".into(),
+            scale: scale(
+                15.0,
+                "ms",
+                &[
+                    (2.0, "Fast"),
+                    (6.0, ""),
+                    (12.0, ""),
+                    (30.0, ""),
+                    (60.0, ""),
+                    (90.0, ""),
+                    (120.0, "Slow"),
+                ],
+            ),
+        },
+        SettingDef {
+            id: "initial_key_repeat".into(),
+            domain: NSGLOBAL.into(),
+            key: "InitialKeyRepeat".into(),
             value_type: ValueType::Int,
             constraint: int_range(0, 120),
-            description: "Delay until repeat (lower = shorter)",
+            factory_default: SettingValue::Int(25),
+            description: "Delay until repeat (lower = shorter)".into(),
             group: SettingGroup::Keyboard,
-            mirror_domains: &[],
-            requires_logout: true,
-            help: "Delay before key starts repeating. Lower = shorter delay",
-        },
-        SettingDef {
-            id: "press_and_hold",
-            domain: NSGLOBAL,
-            key: "ApplePressAndHoldEnabled",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Logout,
+            help: "Delay before key starts repeating. Lower = shorter delay".into(),
+            scale: scale(
+                15.0,
+                "ms",
+                &[
+                    (15.0, "Short"),
+                    (25.0, ""),
+                    (35.0, ""),
+                    (68.0, ""),
+                    (94.0, ""),
+                    (120.0, "Long"),
+                ],
+            ),
+        },
+        SettingDef {
+            id: "press_and_hold".into(),
+            domain: NSGLOBAL.into(),
+            key: "ApplePressAndHoldEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Press-and-hold for accents (off = key repeat)",
+            factory_default: SettingValue::Bool(true),
+            description: "Press-and-hold for accents (off = key repeat)".into(),
             group: SettingGroup::Keyboard,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "On: shows accent menu. Off: key repeats instead",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "On: shows accent menu. Off: key repeats instead".into(),
+            scale: None,
         },
         SettingDef {
-            id: "fn_key_state",
-            domain: NSGLOBAL,
-            key: "com.apple.keyboard.fnState",
+            id: "fn_key_state".into(),
+            domain: NSGLOBAL.into(),
+            key: "com.apple.keyboard.fnState".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Fn key shows F1-F12",
+            factory_default: SettingValue::Bool(false),
+            description: "Fn key shows F1-F12".into(),
             group: SettingGroup::Keyboard,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "On: Fn keys are F1-F12. Off: media/special keys",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::ActivateSettings,
+            help: "On: Fn keys are F1-F12. Off: media/special keys".into(),
+            scale: None,
         },
         // ── Group 8: Text Input ──
         SettingDef {
-            id: "auto_correct",
-            domain: NSGLOBAL,
-            key: "NSAutomaticSpellingCorrectionEnabled",
+            id: "auto_correct".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticSpellingCorrectionEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Auto-correct",
+            factory_default: SettingValue::Bool(true),
+            description: "Auto-correct".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Automatically fixes spelling mistakes",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Automatically fixes spelling mistakes".into(),
+            scale: None,
         },
         SettingDef {
-            id: "auto_capitalize",
-            domain: NSGLOBAL,
-            key: "NSAutomaticCapitalizationEnabled",
+            id: "auto_capitalize".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticCapitalizationEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Auto-capitalization",
+            factory_default: SettingValue::Bool(true),
+            description: "Auto-capitalization".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Capitalizes first letter of sentences",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Capitalizes first letter of sentences".into(),
+            scale: None,
         },
         SettingDef {
-            id: "smart_dashes",
-            domain: NSGLOBAL,
-            key: "NSAutomaticDashSubstitutionEnabled",
+            id: "smart_dashes".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticDashSubstitutionEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Smart dashes",
+            factory_default: SettingValue::Bool(true),
+            description: "Smart dashes".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Converts double hyphens to em dashes",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Converts double hyphens to em dashes".into(),
+            scale: None,
         },
         SettingDef {
-            id: "smart_quotes",
-            domain: NSGLOBAL,
-            key: "NSAutomaticQuoteSubstitutionEnabled",
+            id: "smart_quotes".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticQuoteSubstitutionEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Smart quotes",
+            factory_default: SettingValue::Bool(true),
+            description: "Smart quotes".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Converts straight quotes to curly quotes",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Converts straight quotes to curly quotes".into(),
+            scale: None,
         },
         SettingDef {
-            id: "period_substitution",
-            domain: NSGLOBAL,
-            key: "NSAutomaticPeriodSubstitutionEnabled",
+            id: "period_substitution".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticPeriodSubstitutionEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Double-space to period",
+            factory_default: SettingValue::Bool(true),
+            description: "Double-space to period".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Typing two spaces inserts a period",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Typing two spaces inserts a period".into(),
+            scale: None,
         },
         SettingDef {
-            id: "auto_text_completion",
-            domain: NSGLOBAL,
-            key: "NSAutomaticTextCompletionEnabled",
+            id: "auto_text_completion".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSAutomaticTextCompletionEnabled".into(),
             value_type: ValueType::Bool,
             constraint: Constraint::None,
-            description: "Inline text completion",
+            factory_default: SettingValue::Bool(true),
+            description: "Inline text completion".into(),
+            group: SettingGroup::KeyboardText,
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Immediate,
+            help: "Suggests words as you type".into(),
+            scale: None,
+        },
+        SettingDef {
+            id: "text_replacements".into(),
+            domain: NSGLOBAL.into(),
+            key: "NSUserDictionaryReplacementItems".into(),
+            value_type: ValueType::Array,
+            constraint: Constraint::None,
+            factory_default: SettingValue::Array(vec![SettingValue::Dict(BTreeMap::from([
+                ("on".to_string(), SettingValue::Int(1)),
+                ("replace".to_string(), SettingValue::Str("omw".to_string())),
+                (
+                    "with".to_string(),
+                    SettingValue::Str("On my way!".to_string()),
+                ),
+            ]))]),
+            description: "Text replacements".into(),
             group: SettingGroup::KeyboardText,
-            mirror_domains: &[],
-            requires_logout: false,
-            help: "Suggests words as you type",
+            mirror_domains: vec![],
+            host_scope: HostScope::AnyHost,
+            activation: Activation::Logout,
+            help: "Shortcuts expanded as you type, e.g. omw → On my way!".into(),
+            scale: None,
         },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_defaults_fit_type_and_constraint() {
+        for def in all_settings() {
+            let fits = match (&def.factory_default, &def.constraint) {
+                (SettingValue::Float(v), Constraint::FloatRange(r)) => (r.min..=r.max).contains(v),
+                (SettingValue::Int(v), Constraint::IntRange(r)) => (r.min..=r.max).contains(v),
+                (SettingValue::Str(v), Constraint::StringOptions(opts)) => {
+                    opts.iter().any(|o| o == v)
+                }
+                (value, Constraint::Enum(options)) => options.iter().any(|o| o.value == *value),
+                (SettingValue::Bool(_), Constraint::None) => def.value_type == ValueType::Bool,
+                (SettingValue::Array(_) | SettingValue::Dict(_), Constraint::None) => {
+                    def.factory_default.value_type() == def.value_type
+                }
+                _ => false,
+            };
+            assert!(
+                fits,
+                "{}: factory default {:?} doesn't fit",
+                def.id, def.factory_default
+            );
+        }
+    }
+}
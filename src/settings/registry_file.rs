@@ -0,0 +1,331 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::plist_value::to_setting_value;
+use super::scale::Scale;
+use super::{
+    Activation, Constraint, EnumOption, FloatRange, HostScope, IntRange, SettingDef, SettingGroup,
+    ValueType,
+};
+
+/// Processes a registry file may ask to restart after a write.
+const RESTARTABLE: &[&str] = &["Dock", "Finder", "SystemUIServer"];
+
+/// One setting in a registry file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Entry {
+    id: String,
+    domain: String,
+    key: String,
+    #[serde(rename = "type")]
+    value_type: ValueType,
+    #[serde(default)]
+    constraint: Option<EntryConstraint>,
+    default: plist::Value,
+    description: String,
+    group: SettingGroup,
+    #[serde(default)]
+    mirrors: Vec<String>,
+    #[serde(default)]
+    host: HostScope,
+    #[serde(default)]
+    activation: EntryActivation,
+    help: String,
+    #[serde(default)]
+    scale: Option<Scale>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+enum EntryConstraint {
+    IntRange { min: i64, max: i64 },
+    FloatRange { min: f64, max: f64, step: f64 },
+    Options(Vec<String>),
+    Enum(Vec<EntryOption>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryOption {
+    value: plist::Value,
+    label: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum EntryActivation {
+    #[default]
+    Immediate,
+    FlushPreferences,
+    ActivateSettings,
+    Restart(String),
+    Logout,
+}
+
+/// The settings declared by one file in `registry.d`.
+#[derive(Debug)]
+pub struct RegistryFile {
+    pub path: PathBuf,
+    pub defs: Vec<SettingDef>,
+}
+
+/// A registry file that couldn't be read or parsed.
+#[derive(Debug)]
+pub struct BadFile {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// The files in `registry.d`, split by whether they parsed.
+#[derive(Debug, Default)]
+pub struct RegistryDir {
+    pub files: Vec<RegistryFile>,
+    pub bad: Vec<BadFile>,
+}
+
+/// `~/.config/myshenyatko/registry.d`, on macOS too, where the platform
+/// config directory would be `~/Library/Application Support`.
+pub fn registry_dir() -> Option<PathBuf> {
+    Some(
+        dirs::home_dir()?
+            .join(".config")
+            .join("myshenyatko")
+            .join("registry.d"),
+    )
+}
+
+/// Reads every `*.json` file in `dir`, sorted by file name. A file that
+/// fails to parse doesn't stop the others from being read. A missing
+/// directory holds no files.
+pub fn read_dir(dir: &Path) -> Result<RegistryDir> {
+    let mut read = RegistryDir::default();
+    if !dir.is_dir() {
+        return Ok(read);
+    }
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).context(format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    for path in paths {
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| parse(&json))
+        {
+            Ok(defs) => read.files.push(RegistryFile { path, defs }),
+            Err(error) => read.bad.push(BadFile { path, error }),
+        }
+    }
+    Ok(read)
+}
+
+/// Parses a registry file: a JSON array of settings.
+pub fn parse(json: &str) -> Result<Vec<SettingDef>> {
+    let entries: Vec<Entry> = serde_json::from_str(json)?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let id = entry.id.clone();
+            to_def(entry).context(id)
+        })
+        .collect()
+}
+
+fn to_def(entry: Entry) -> Result<SettingDef> {
+    let factory_default = to_setting_value(&entry.default, entry.value_type)
+        .map_err(|e| anyhow::anyhow!("default: {e}"))?;
+    let constraint = match entry.constraint {
+        Some(EntryConstraint::IntRange { min, max }) => Constraint::IntRange(IntRange { min, max }),
+        Some(EntryConstraint::FloatRange { min, max, step }) => {
+            Constraint::FloatRange(FloatRange { min, max, step })
+        }
+        Some(EntryConstraint::Options(options)) => {
+            Constraint::StringOptions(options.into_iter().map(Into::into).collect())
+        }
+        Some(EntryConstraint::Enum(options)) => Constraint::Enum(
+            options
+                .into_iter()
+                .map(|option| {
+                    let value = to_setting_value(&option.value, entry.value_type)
+                        .map_err(|e| anyhow::anyhow!("option '{}': {e}", option.label))?;
+                    Ok(EnumOption {
+                        value,
+                        label: option.label.into(),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        ),
+        None => Constraint::None,
+    };
+    let activation = match entry.activation {
+        EntryActivation::Immediate => Activation::Immediate,
+        EntryActivation::FlushPreferences => Activation::FlushPreferences,
+        EntryActivation::ActivateSettings => Activation::ActivateSettings,
+        EntryActivation::Logout => Activation::Logout,
+        EntryActivation::Restart(process) => {
+            let process = RESTARTABLE
+                .iter()
+                .find(|p| **p == process)
+                .with_context(|| {
+                    format!(
+                        "can't restart {process}; expected one of {}",
+                        RESTARTABLE.join(", ")
+                    )
+                })?;
+            Activation::Restart(process)
+        }
+    };
+    Ok(SettingDef {
+        id: entry.id.into(),
+        domain: entry.domain.into(),
+        key: entry.key.into(),
+        value_type: entry.value_type,
+        constraint,
+        factory_default,
+        description: entry.description.into(),
+        group: entry.group,
+        mirror_domains: entry.mirrors.into_iter().map(Into::into).collect(),
+        host_scope: entry.host,
+        activation,
+        help: entry.help.into(),
+        scale: entry.scale,
+    })
+}
+
+/// Adds the settings from registry files to `defs`. A file may redefine a
+/// built-in setting by using its id, but two files can't define the same id,
+/// and no two settings may share a domain and key.
+pub fn merge(defs: &mut Vec<SettingDef>, files: Vec<RegistryFile>) -> Result<()> {
+    let mut defined_in: Vec<(String, PathBuf)> = Vec::new();
+    for file in files {
+        for def in file.defs {
+            if let Some((_, other)) = defined_in.iter().find(|(id, _)| *id == def.id) {
+                anyhow::bail!(
+                    "{}: '{}' is already defined in {}",
+                    file.path.display(),
+                    def.id,
+                    other.display()
+                );
+            }
+            if let Some(clash) = defs
+                .iter()
+                .find(|d| d.id != def.id && d.domain == def.domain && d.key == def.key)
+            {
+                anyhow::bail!(
+                    "{}: '{}' uses {} {}, which belongs to '{}'",
+                    file.path.display(),
+                    def.id,
+                    def.domain,
+                    def.key,
+                    clash.id
+                );
+            }
+            defined_in.push((def.id.to_string(), file.path.clone()));
+            match defs.iter_mut().find(|d| d.id == def.id) {
+                Some(existing) => *existing = def,
+                None => defs.push(def),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SettingValue;
+    use crate::settings::registry::all_settings;
+
+    fn file(name: &str, json: &str) -> RegistryFile {
+        RegistryFile {
+            path: PathBuf::from(name),
+            defs: parse(json).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parses_entries_and_checks_restarts() {
+        let defs = parse(
+            r#"[{
+                "id": "dock_spring_delay", "domain": "com.apple.dock", "key": "springboard-delay",
+                "type": "float", "constraint": {"floatRange": {"min": 0, "max": 2, "step": 0.5}},
+                "default": 1, "description": "Spring delay", "group": "ScrollWindow",
+                "activation": {"restart": "Dock"}, "help": "Seconds before a folder springs open"
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(defs[0].factory_default, SettingValue::Float(1.0));
+        assert_eq!(defs[0].activation, Activation::Restart("Dock"));
+        assert!(matches!(
+            defs[0].constraint,
+            Constraint::FloatRange(FloatRange { step: 0.5, .. })
+        ));
+
+        let bad = r#"[{"id": "x", "domain": "d", "key": "k", "type": "bool", "default": true,
+            "description": "", "group": "Mouse", "activation": {"restart": "launchd"}, "help": ""}]"#;
+        assert!(parse(bad).is_err());
+    }
+
+    #[test]
+    fn parses_named_values() {
+        let defs = parse(
+            r#"[{
+                "id": "dock_orientation", "domain": "com.apple.dock", "key": "orientation", "type": "str",
+                "constraint": {"enum": [{"value": "left", "label": "Left"}, {"value": "bottom", "label": "Bottom"}]},
+                "default": "bottom", "description": "Dock position", "group": "ScrollWindow", "help": "h"
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            defs[0].label(&SettingValue::Str("left".into())),
+            Some("Left")
+        );
+        assert_eq!(defs[0].label(&SettingValue::Str("right".into())), None);
+
+        let mismatched = r#"[{"id": "x", "domain": "d", "key": "k", "type": "int", "default": 0,
+            "constraint": {"enum": [{"value": "zero", "label": "Zero"}]},
+            "description": "", "group": "Mouse", "help": ""}]"#;
+        assert!(parse(mismatched).is_err());
+    }
+
+    #[test]
+    fn files_override_built_ins_and_conflicts_fail() {
+        let entry = |id: &str, key: &str| {
+            format!(
+                r#"[{{"id": "{id}", "domain": "NSGlobalDomain", "key": "{key}", "type": "int",
+                    "default": 1, "description": "d", "group": "Keyboard", "help": "h"}}]"#
+            )
+        };
+        let mut defs = all_settings();
+        let count = defs.len();
+        merge(
+            &mut defs,
+            vec![
+                file("a.json", &entry("key_repeat", "KeyRepeat")),
+                file("b.json", &entry("new_key", "NewKey")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(defs.len(), count + 1);
+        let repeat = defs.iter().find(|d| d.id == "key_repeat").unwrap();
+        assert_eq!(repeat.factory_default, SettingValue::Int(1));
+
+        let twice = vec![
+            file("a.json", &entry("new_key", "NewKey")),
+            file("b.json", &entry("new_key", "NewKey")),
+        ];
+        let err = merge(&mut all_settings(), twice).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "b.json: 'new_key' is already defined in a.json"
+        );
+        let taken = vec![file("a.json", &entry("my_repeat", "KeyRepeat"))];
+        assert!(merge(&mut all_settings(), taken).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Deserialize;
+
+use super::{SettingValue, Text};
+
+/// How a number is shown to people, and where its System Settings slider
+/// stops.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scale {
+    /// Converts the stored value to `unit`, e.g. 15 for `KeyRepeat` in ms.
+    pub factor: f64,
+    #[serde(default)]
+    pub unit: Text,
+    /// Slider positions as stored values, in ascending order. Empty when
+    /// System Settings has no slider for the setting.
+    #[serde(default)]
+    pub stops: Vec<Stop>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stop {
+    pub value: f64,
+    /// The slider's caption at this stop, such as "Fast".
+    #[serde(default)]
+    pub label: Option<Text>,
+}
+
+impl Scale {
+    /// `30 ms (Fast)` for a stored `KeyRepeat` of 2.
+    pub fn describe(&self, value: f64) -> String {
+        let amount = trim_number(value * self.factor);
+        let mut text = if self.unit.is_empty() {
+            amount
+        } else {
+            format!("{amount} {}", self.unit)
+        };
+        if let Some(label) = self.stop_at(value).and_then(|stop| stop.label.as_ref()) {
+            text.push_str(&format!(" ({label})"));
+        }
+        text
+    }
+
+    /// Whether System Settings can't set this value.
+    pub fn beyond_ui(&self, value: f64) -> bool {
+        match (self.stops.first(), self.stops.last()) {
+            (Some(low), Some(high)) => value < low.value - EPSILON || value > high.value + EPSILON,
+            _ => false,
+        }
+    }
+
+    /// The nearest stop past `value` in `direction`, if there is one.
+    pub fn next_stop(&self, value: f64, direction: i32) -> Option<f64> {
+        let mut stops = self.stops.iter().map(|stop| stop.value);
+        if direction > 0 {
+            stops.find(|stop| *stop > value + EPSILON)
+        } else {
+            stops.rev().find(|stop| *stop < value - EPSILON)
+        }
+    }
+
+    fn stop_at(&self, value: f64) -> Option<&Stop> {
+        self.stops
+            .iter()
+            .find(|stop| (stop.value - value).abs() <= EPSILON)
+    }
+}
+
+const EPSILON: f64 = 1e-6;
+
+/// Numeric settings as a float, for scaling.
+pub fn as_number(value: &SettingValue) -> Option<f64> {
+    match value {
+        SettingValue::Float(v) => Some(*v),
+        SettingValue::Int(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+/// At most two decimals, without trailing zeros.
+fn trim_number(v: f64) -> String {
+    let s = format!("{v:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn describes_and_steps_through_stops() {
+        let defs = all_settings();
+        let scale = |id: &str| {
+            defs.iter()
+                .find(|d| d.id == id)
+                .unwrap()
+                .scale
+                .clone()
+                .unwrap()
+        };
+
+        let repeat = scale("key_repeat");
+        assert_eq!(repeat.describe(2.0), "30 ms (Fast)");
+        assert_eq!(repeat.describe(5.0), "75 ms");
+        assert!(repeat.beyond_ui(1.0));
+        assert_eq!(repeat.next_stop(5.0, 1), Some(6.0));
+        assert_eq!(repeat.next_stop(5.0, -1), Some(2.0));
+        assert_eq!(repeat.next_stop(120.0, 1), None);
+
+        let tracking = scale("mouse_tracking_speed");
+        assert_eq!(tracking.describe(3.0), "3 (Fast)");
+        assert!(tracking.beyond_ui(3.5));
+        assert!(!tracking.beyond_ui(0.6875));
+    }
+}
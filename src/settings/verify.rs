@@ -0,0 +1,136 @@
+use std::fmt;
+
+use super::backend::PreferencesBackend;
+use super::{Host, SettingChange, SettingDef};
+
+/// A setting whose value didn't read back as written, e.g. because a
+/// configuration profile or a running app put its own value back.
+#[derive(Debug, Clone)]
+pub struct VerifyFailure {
+    pub id: String,
+    pub description: String,
+    /// One entry per domain that didn't match.
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for VerifyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.description, self.problems.join("; "))
+    }
+}
+
+/// Re-reads every location an applied change touched and lists the settings
+/// that don't hold the value that was written.
+pub fn verify_changes(
+    backend: &dyn PreferencesBackend,
+    changes: &[(&SettingDef, SettingChange)],
+) -> Vec<VerifyFailure> {
+    changes
+        .iter()
+        .filter_map(|(def, change)| {
+            let problems: Vec<String> = def
+                .locations()
+                .into_iter()
+                .filter_map(|(domain, host)| check(backend, def, domain, host, change))
+                .collect();
+            (!problems.is_empty()).then(|| VerifyFailure {
+                id: def.id.to_string(),
+                description: def.description.to_string(),
+                problems,
+            })
+        })
+        .collect()
+}
+
+fn check(
+    backend: &dyn PreferencesBackend,
+    def: &SettingDef,
+    domain: &str,
+    host: Host,
+    change: &SettingChange,
+) -> Option<String> {
+    let location = match host {
+        Host::Any => domain.to_string(),
+        Host::Current => format!("{domain} (currentHost)"),
+    };
+    let found = backend.read(domain, host, &def.key, def.value_type);
+    match (change, found) {
+        (SettingChange::Set(expected), Ok(found)) if !found.approx_eq(expected) => {
+            Some(format!("{location} is {found}, expected {expected}"))
+        }
+        (SettingChange::Set(expected), Err(e)) => {
+            Some(format!("{location} unreadable, expected {expected}: {e}"))
+        }
+        (SettingChange::Reset, Ok(found)) => Some(format!("{location} is still {found}")),
+        (SettingChange::Reset, Err(e)) if !e.is_not_set() => {
+            Some(format!("{location} is still set: {e}"))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SettingValue;
+    use crate::settings::backend::MemoryBackend;
+    use crate::settings::registry::all_settings;
+    use crate::settings::writer::write_setting;
+
+    #[test]
+    fn passes_when_values_read_back() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let speed = defs
+            .iter()
+            .find(|d| d.id == "trackpad_tracking_speed")
+            .unwrap();
+        write_setting(&backend, speed, &SettingValue::Float(0.1 + 0.2)).unwrap();
+
+        let failures = verify_changes(
+            &backend,
+            &[(speed, SettingChange::Set(SettingValue::Float(0.3)))],
+        );
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn reports_overwritten_mirror_and_lingering_reset() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let clicking = defs.iter().find(|d| d.id == "tp_clicking").unwrap();
+        let repeat = defs.iter().find(|d| d.id == "key_repeat").unwrap();
+        write_setting(&backend, clicking, &SettingValue::Bool(true)).unwrap();
+        // Something else puts the old value back in one mirror domain.
+        backend
+            .write(
+                &clicking.mirror_domains[0],
+                Host::Any,
+                &clicking.key,
+                &SettingValue::Bool(false),
+            )
+            .unwrap();
+        backend
+            .write(
+                &repeat.domain,
+                Host::Any,
+                &repeat.key,
+                &SettingValue::Int(2),
+            )
+            .unwrap();
+
+        let failures = verify_changes(
+            &backend,
+            &[
+                (clicking, SettingChange::Set(SettingValue::Bool(true))),
+                (repeat, SettingChange::Reset),
+            ],
+        );
+
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].id, "tp_clicking");
+        assert_eq!(failures[0].problems.len(), 1);
+        assert!(failures[0].problems[0].starts_with(&*clicking.mirror_domains[0]));
+        assert_eq!(failures[1].problems, vec!["NSGlobalDomain is still 2"]);
+    }
+}
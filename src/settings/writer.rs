@@ -1,45 +1,114 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use anyhow::Result;
 
-use super::{SettingDef, SettingValue, ValueType};
+use super::backend::PreferencesBackend;
+use super::{Host, SettingDef, SettingValue, ValueType};
 
-pub fn write_setting(def: &SettingDef, value: &SettingValue) -> Result<()> {
-    write_to_domain(def.domain, def.key, def.value_type, value)?;
-    for mirror in def.mirror_domains {
-        write_to_domain(mirror, def.key, def.value_type, value)?;
+pub fn write_setting(
+    backend: &dyn PreferencesBackend,
+    def: &SettingDef,
+    value: &SettingValue,
+) -> Result<()> {
+    for (domain, host) in def.locations() {
+        write_to_domain(backend, domain, host, &def.key, def.value_type, value)?;
     }
     Ok(())
 }
 
-fn write_to_domain(domain: &str, key: &str, vtype: ValueType, value: &SettingValue) -> Result<()> {
-    let (type_flag, str_value) = match value {
-        SettingValue::Float(v) => ("-float", v.to_string()),
-        SettingValue::Bool(v) => ("-bool", if *v { "TRUE" } else { "FALSE" }.to_string()),
-        SettingValue::Int(v) => ("-int", v.to_string()),
-        SettingValue::Str(v) => {
-            if vtype == ValueType::Str {
-                ("-string", v.clone())
-            } else {
-                anyhow::bail!("type mismatch: expected {vtype:?}, got string");
-            }
-        }
-    };
-
-    let output = Command::new("defaults")
-        .arg("write")
-        .arg(domain)
-        .arg(key)
-        .arg(type_flag)
-        .arg(&str_value)
-        .output()
-        .context("failed to execute defaults write")?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "defaults write failed for {domain}.{key}: {}",
-            String::from_utf8_lossy(&output.stderr).trim()
-        );
+/// Deletes the key from the primary and mirror domains, on every host in scope.
+pub fn reset_setting(backend: &dyn PreferencesBackend, def: &SettingDef) -> Result<()> {
+    for (domain, host) in def.locations() {
+        backend.delete(domain, host, &def.key)?;
     }
+    Ok(())
+}
 
+fn write_to_domain(
+    backend: &dyn PreferencesBackend,
+    domain: &str,
+    host: Host,
+    key: &str,
+    vtype: ValueType,
+    value: &SettingValue,
+) -> Result<()> {
+    check_type(vtype, value)?;
+    backend.write(domain, host, key, value)
+}
+
+/// Numbers and bools are interchangeable in plists, strings and
+/// collections are not.
+pub(super) fn check_type(vtype: ValueType, value: &SettingValue) -> Result<()> {
+    let found = value.value_type();
+    let loose = |t| matches!(t, ValueType::Float | ValueType::Bool | ValueType::Int);
+    if found != vtype && !(loose(found) && loose(vtype)) {
+        anyhow::bail!("type mismatch: expected {vtype:?}, got {found:?}");
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::backend::MemoryBackend;
+    use crate::settings::registry::all_settings;
+
+    #[test]
+    fn writes_mirror_domains() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "tp_clicking").unwrap();
+
+        write_setting(&backend, def, &SettingValue::Bool(true)).unwrap();
+
+        let store = backend.snapshot();
+        for domain in std::iter::once(&def.domain).chain(&def.mirror_domains) {
+            assert_eq!(
+                store.any_host[&**domain][&*def.key],
+                SettingValue::Bool(true)
+            );
+        }
+    }
+
+    #[test]
+    fn reset_deletes_mirror_domains() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "tp_clicking").unwrap();
+
+        write_setting(&backend, def, &SettingValue::Bool(true)).unwrap();
+        reset_setting(&backend, def).unwrap();
+
+        let store = backend.snapshot();
+        for domain in std::iter::once(&def.domain).chain(&def.mirror_domains) {
+            assert!(!store.any_host[&**domain].contains_key(&*def.key));
+        }
+        reset_setting(&backend, def).unwrap();
+    }
+
+    #[test]
+    fn rejects_string_for_typed_setting() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "key_repeat").unwrap();
+
+        assert!(write_setting(&backend, def, &SettingValue::Str("2".into())).is_err());
+        assert!(write_setting(&backend, def, &SettingValue::Array(vec![])).is_err());
+        assert_eq!(backend.snapshot(), Default::default());
+    }
+
+    #[test]
+    fn writes_every_host_in_scope() {
+        let backend = MemoryBackend::default();
+        let defs = all_settings();
+        let def = defs.iter().find(|d| d.id == "tap_to_click").unwrap();
+
+        write_setting(&backend, def, &SettingValue::Int(1)).unwrap();
+
+        let store = backend.snapshot();
+        for host in [Host::Any, Host::Current] {
+            assert_eq!(
+                store.host(host)[&*def.domain][&*def.key],
+                SettingValue::Int(1)
+            );
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use crate::app::App;
+use crate::settings::backend::ReadError;
+use crate::settings::diff::nested_changes;
+use crate::settings::{SettingChange, SettingValue};
 
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let diffs = app.pending_diffs();
@@ -13,26 +16,13 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let warning_lines = if app.any_requires_logout() { 2 } else { 0 };
-    let row_count = diffs.len() + warning_lines;
-    let constraints: Vec<Constraint> = (0..row_count)
-        .map(|_| Constraint::Length(1))
-        .chain(std::iter::once(Constraint::Min(0)))
-        .collect();
-
-    let rows = Layout::default()
-        .constraints(constraints)
-        .split(inner);
-
-    for (i, (def, old, new)) in diffs.iter().enumerate() {
-        if i >= rows.len() {
-            break;
-        }
+    let mut lines = Vec::new();
+    for (def, old, new) in &diffs {
         let old_display = match old {
             Some(val) => format!("{val}"),
             None => "Not set".to_string(),
         };
-        let line = Line::from(vec![
+        lines.push(Line::from(vec![
             Span::styled(
                 format!("  {:<36}", def.description),
                 Style::default().fg(Color::White),
@@ -40,25 +30,157 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled(old_display, Style::default().fg(Color::Red)),
             Span::styled("  →  ", Style::default().fg(Color::DarkGray)),
             Span::styled(format!("{new}"), Style::default().fg(Color::Green)),
-            if def.requires_logout {
-                Span::styled("  (logout required)", Style::default().fg(Color::Yellow))
-            } else {
-                Span::raw("")
+            match def.activation.label() {
+                Some(label) if def.requires_logout() => {
+                    Span::styled(format!("  ({label})"), Style::default().fg(Color::Yellow))
+                }
+                Some(label) => {
+                    Span::styled(format!("  ({label})"), Style::default().fg(Color::DarkGray))
+                }
+                None => Span::raw(""),
             },
-        ]);
-        frame.render_widget(Paragraph::new(line), rows[i]);
+        ]));
+
+        if app.is_stale(&def.id) {
+            let base = match app.pending_base.get(&*def.id) {
+                Some(Ok(val)) => format!("{val}"),
+                Some(Err(e)) if !e.is_not_set() => e.to_string(),
+                _ => "Not set".to_string(),
+            };
+            lines.push(Line::from(Span::styled(
+                format!("      ⚠ changed outside since you edited it (was {base})"),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+
+        // Arrays and dictionaries also list which elements changed.
+        if let (Some(old), SettingChange::Set(new)) = (old, new)
+            && matches!(new, SettingValue::Array(_) | SettingValue::Dict(_))
+        {
+            for change in nested_changes(old, new) {
+                lines.push(nested_line(
+                    &change.path,
+                    change.old.as_ref(),
+                    change.new.as_ref(),
+                ));
+            }
+        }
     }
 
     if app.any_requires_logout() {
-        let warn_idx = diffs.len();
-        if warn_idx + 1 < rows.len() {
-            let warning = Line::from(Span::styled(
-                "  ⚠  Some changes require logout to take effect",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ));
-            frame.render_widget(Paragraph::new(warning), rows[warn_idx + 1]);
-        }
+        lines.push(Line::raw(""));
+        lines.push(Line::from(Span::styled(
+            "  ⚠  Some changes require logout to take effect",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
     }
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn nested_line(
+    path: &str,
+    old: Option<&SettingValue>,
+    new: Option<&SettingValue>,
+) -> Line<'static> {
+    let path = Span::styled(
+        format!("      {path}  "),
+        Style::default().fg(Color::DarkGray),
+    );
+    match (old, new) {
+        (Some(old), Some(new)) => Line::from(vec![
+            path,
+            Span::styled(format!("{old}"), Style::default().fg(Color::Red)),
+            Span::styled("  →  ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{new}"), Style::default().fg(Color::Green)),
+        ]),
+        (None, Some(new)) => Line::from(vec![
+            path,
+            Span::styled(format!("+ {new}"), Style::default().fg(Color::Green)),
+        ]),
+        (Some(old), None) => Line::from(vec![
+            path,
+            Span::styled(format!("- {old}"), Style::default().fg(Color::Red)),
+        ]),
+        (None, None) => Line::from(path),
+    }
+}
+
+/// Asks how to settle the first change whose setting was modified elsewhere.
+pub fn draw_conflict(frame: &mut Frame, app: &App, area: Rect) {
+    let title = format!(" Changed Outside ({} to resolve) ", app.conflicts.len());
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let Some(conflict) = app.conflicts.first() else {
+        return;
+    };
+    let show = |v: &Result<SettingValue, ReadError>| match v {
+        Ok(v) => format!("{v}"),
+        Err(e) if e.is_not_set() => "Not set".to_string(),
+        Err(e) => e.to_string(),
+    };
+    let yours = match app.pending_changes.get(&conflict.id) {
+        Some(SettingChange::Set(v)) => format!("{v}"),
+        _ => "Default".to_string(),
+    };
+    let row = |label: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(
+                format!("  {label:<12}"),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+
+    let lines = vec![
+        Line::from(Span::styled(
+            format!("  {} was changed after you edited it", conflict.description),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::raw(""),
+        row("You saw", show(&conflict.seen), Color::White),
+        row("Now", show(&conflict.current), Color::Red),
+        row("Your change", yours, Color::Green),
+    ];
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Lists the exact `defaults` commands applying would run.
+pub fn draw_dry_run(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Dry Run ");
+    let lines = match app.pending_plan() {
+        Ok(plan) => plan
+            .operations
+            .iter()
+            .map(|op| {
+                Line::from(Span::styled(
+                    format!("  {op}"),
+                    Style::default().fg(Color::White),
+                ))
+            })
+            .chain(plan.activations.iter().map(|command| {
+                Line::from(Span::styled(
+                    format!("  {command}"),
+                    Style::default().fg(Color::DarkGray),
+                ))
+            }))
+            .collect(),
+        Err(e) => vec![Line::from(Span::styled(
+            format!("  {e}"),
+            Style::default().fg(Color::Red),
+        ))],
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
 }
@@ -33,6 +33,8 @@ pub fn draw(frame: &mut Frame, app: &App) {
         View::Review => diff::draw(frame, app, chunks[1]),
         View::Profiles => profiles::draw(frame, app, chunks[1]),
         View::ProfileNameInput => profiles::draw_name_input(frame, app, chunks[1]),
+        View::Conflict => diff::draw_conflict(frame, app, chunks[1]),
+        View::DryRun => diff::draw_dry_run(frame, app, chunks[1]),
     }
 
     draw_status_bar(frame, app, chunks[2]);
@@ -91,10 +93,14 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let keybinds = match app.view {
-        View::Settings => "[Tab] switch  [↑↓] navigate  [←→] adjust  [Space] toggle  [r]eview  [p]rofiles  [q]uit",
-        View::Review => "[a]pply  [c]ancel  [s]ave profile  [Esc] back",
+        View::Settings => {
+            "[Tab] switch  [↑↓] navigate  [←→] adjust  [Space] toggle  [S]nap  [x] reset  [X] reset group  [r]eview  [p]rofiles  [q]uit"
+        }
+        View::Review => "[a]pply  [D]ry run  [c]ancel  [s]ave profile  [Esc] back",
         View::Profiles => "[↑↓] select  [Enter] apply  [n]ew  [d]elete  [Esc] back",
         View::ProfileNameInput => "[Enter] confirm  [Esc] cancel",
+        View::Conflict => "[t] keep theirs  [o]verwrite  [s]kip  [Esc] back",
+        View::DryRun => "[Esc] back",
     };
 
     let bar = Paragraph::new(Line::from(vec![
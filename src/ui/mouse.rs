@@ -23,10 +23,9 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
         .split(inner);
 
     let mut row_idx = 0;
-    let mut setting_idx = 0;
     let mut last_group = None;
 
-    for def in &visible {
+    for (setting_idx, def) in visible.iter().enumerate() {
         if last_group != Some(def.group) {
             if row_idx < rows.len() {
                 let header = match def.group {
@@ -41,11 +40,14 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
         }
 
         if row_idx < rows.len() {
-            let is_changed = app.pending_changes.contains_key(def.id);
-            let value = app.effective_value(def.id);
-            render_setting_row(frame, rows[row_idx], def, value, is_changed, setting_idx == app.selected_row);
+            render_setting_row(
+                frame,
+                rows[row_idx],
+                app,
+                def,
+                setting_idx == app.selected_row,
+            );
             row_idx += 1;
         }
-        setting_idx += 1;
     }
 }
@@ -4,16 +4,18 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 
+use crate::app::App;
+use crate::settings::scale::as_number;
 use crate::settings::{Constraint, FloatRange, SettingDef, SettingValue};
 
 pub fn render_setting_row(
     frame: &mut Frame,
     area: Rect,
+    app: &App,
     def: &SettingDef,
-    value: Option<&SettingValue>,
-    is_changed: bool,
     is_selected: bool,
 ) {
+    let is_changed = app.pending_changes.contains_key(&*def.id);
     let label_style = if is_selected {
         Style::default()
             .fg(Color::Cyan)
@@ -33,34 +35,63 @@ pub fn render_setting_row(
     let cursor = if is_selected { "▸ " } else { "  " };
     let label = format!("{}{:<40}", cursor, def.description);
 
-    let value_display = match value {
-        Some(val) => format_value(val, &def.constraint),
-        None => "—".to_string(),
+    let (value_display, value_style) = match (app.effective_value(&def.id), app.read_error(&def.id))
+    {
+        (Some(val), _) => (format_value(val, def), value_style),
+        (None, Some(e)) if !e.is_not_set() => {
+            (format!("unreadable ({e})"), Style::default().fg(Color::Red))
+        }
+        (None, _) => ("not set".to_string(), Style::default().fg(Color::DarkGray)),
     };
 
+    let beyond_marker = match (&def.scale, app.effective_value(&def.id).and_then(as_number)) {
+        (Some(scale), Some(v)) if scale.beyond_ui(v) => " beyond UI range",
+        _ => "",
+    };
     let changed_marker = if is_changed { " *" } else { "" };
+    let drift_marker = match app.drifted.get(&*def.id) {
+        Some(found) if !is_changed => format!(" (stored as {found})"),
+        _ => String::new(),
+    };
+    let external_marker = if app.externally_changed.contains(&*def.id) {
+        " (changed outside)"
+    } else {
+        ""
+    };
 
+    let default_display = format_plain(&def.factory_default, def);
     let help_text = if !def.help.is_empty() {
-        format!("  {}", def.help)
+        format!("  {} (default: {default_display})", def.help)
     } else {
-        String::new()
+        format!("  (default: {default_display})")
+    };
+
+    let trailer = match app.unverified.get(&*def.id) {
+        Some(warning) => Span::styled(
+            format!("  ✗ didn't stick: {warning}"),
+            Style::default().fg(Color::Red),
+        ),
+        None => Span::styled(help_text, Style::default().fg(Color::DarkGray)),
     };
 
     let line = Line::from(vec![
         Span::styled(label, label_style),
         Span::styled(value_display, value_style),
+        Span::styled(beyond_marker, Style::default().fg(Color::LightRed)),
         Span::styled(changed_marker, Style::default().fg(Color::Yellow)),
-        Span::styled(help_text, Style::default().fg(Color::DarkGray)),
+        Span::styled(drift_marker, Style::default().fg(Color::Yellow)),
+        Span::styled(external_marker, Style::default().fg(Color::Magenta)),
+        trailer,
     ]);
 
     frame.render_widget(Paragraph::new(line), area);
 }
 
-fn format_value(val: &SettingValue, constraint: &Constraint) -> String {
-    match (val, constraint) {
+fn format_value(val: &SettingValue, def: &SettingDef) -> String {
+    match (val, &def.constraint) {
         (SettingValue::Float(v), Constraint::FloatRange(FloatRange { min, max, .. })) => {
             let bar = render_slider(*v, *min, *max, 20);
-            format!("{bar} {v:.2}")
+            format!("{bar} {}", format_plain(val, def))
         }
         (SettingValue::Bool(v), _) => {
             if *v {
@@ -69,12 +100,26 @@ fn format_value(val: &SettingValue, constraint: &Constraint) -> String {
                 "[ ] Off".to_string()
             }
         }
-        (SettingValue::Int(v), _) => format!("{v}"),
-        (SettingValue::Str(v), _) => v.clone(),
+        (SettingValue::Int(_), _) => format_plain(val, def),
+        (SettingValue::Str(_), _) => format_plain(val, def),
+        (SettingValue::Array(items), _) => format!("{} items", items.len()),
+        (SettingValue::Dict(entries), _) => format!("{} keys", entries.len()),
         _ => val.to_string(),
     }
 }
 
+/// A value by name, or in the setting's unit with its slider caption.
+fn format_plain(val: &SettingValue, def: &SettingDef) -> String {
+    if let Some(label) = def.label(val) {
+        return label.to_string();
+    }
+    match (val, &def.scale, as_number(val)) {
+        (_, Some(scale), Some(v)) => scale.describe(v),
+        (SettingValue::Float(v), _, _) => format!("{v:.2}"),
+        (other, _, _) => other.to_string(),
+    }
+}
+
 fn render_slider(value: f64, min: f64, max: f64, width: usize) -> String {
     let range = max - min;
     if range <= 0.0 {
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::settings::backend::PreferencesBackend;
+use crate::settings::reader::{Readings, domains, prefetch, read_all};
+use crate::settings::{Host, SettingDef};
+
+/// Domains the backend can't tell the modification time of are re-read
+/// every this many intervals, since each read may run `defaults`.
+const UNDATED_EVERY: u32 = 10;
+
+/// A fresh read of every setting, and when that read started.
+#[derive(Debug, Clone)]
+pub struct Refresh {
+    pub started: Instant,
+    pub readings: Readings,
+}
+
+/// Watches for changes made in System Settings or by other tools on a
+/// background thread, so they show up in the TUI. Every `interval` it
+/// compares each domain's modification time with the last one and re-reads
+/// only the settings in domains that changed; the first check reads them all.
+/// The thread stops once the receiver is dropped.
+pub fn spawn(
+    backend: Arc<dyn PreferencesBackend>,
+    defs: Vec<SettingDef>,
+    interval: Duration,
+) -> Receiver<Refresh> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut modified: HashMap<(&str, Host), Option<SystemTime>> = HashMap::new();
+        let mut readings = Readings::default();
+        for tick in 0u32.. {
+            thread::sleep(interval);
+            let started = Instant::now();
+            let changed: HashSet<(&str, Host)> = domains(&defs)
+                .into_iter()
+                .filter(|&(domain, host)| {
+                    let now = backend.modified(domain, host);
+                    let before = modified.insert((domain, host), now);
+                    before != Some(now) || (now.is_none() && tick % UNDATED_EVERY == 0)
+                })
+                .collect();
+            if changed.is_empty() {
+                continue;
+            }
+            let stale: Vec<SettingDef> = defs
+                .iter()
+                .filter(|d| {
+                    d.host_scope
+                        .hosts()
+                        .iter()
+                        .any(|host| changed.contains(&(&*d.domain, *host)))
+                })
+                .cloned()
+                .collect();
+            backend.invalidate();
+            prefetch(backend.as_ref(), &stale);
+            readings.replace(&stale, read_all(backend.as_ref(), &stale));
+            if tx
+                .send(Refresh {
+                    started,
+                    readings: readings.clone(),
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::backend::{MemoryBackend, ReadError};
+    use crate::settings::plist_value::PlistType;
+    use crate::settings::registry::all_settings;
+    use crate::settings::{SettingValue, ValueType};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn picks_up_external_writes() {
+        let backend: Arc<dyn PreferencesBackend> = Arc::new(MemoryBackend::default());
+        let rx = spawn(backend.clone(), all_settings(), Duration::from_millis(10));
+
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Int(2),
+            )
+            .unwrap();
+
+        let refresh = rx
+            .iter()
+            .find(|r| r.readings.values.contains_key("key_repeat"))
+            .unwrap();
+        assert_eq!(refresh.readings.values["key_repeat"], SettingValue::Int(2));
+    }
+
+    /// Counts reads, and takes its modification times from a backing file.
+    struct CountingBackend {
+        inner: MemoryBackend,
+        reads: AtomicUsize,
+    }
+
+    impl PreferencesBackend for CountingBackend {
+        fn domain_exists(&self, domain: &str, host: Host) -> bool {
+            self.inner.domain_exists(domain, host)
+        }
+
+        fn read(
+            &self,
+            domain: &str,
+            host: Host,
+            key: &str,
+            value_type: ValueType,
+        ) -> Result<SettingValue, ReadError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.inner.read(domain, host, key, value_type)
+        }
+
+        fn stored_type(&self, domain: &str, host: Host, key: &str) -> Result<PlistType, ReadError> {
+            self.inner.stored_type(domain, host, key)
+        }
+
+        fn write(
+            &self,
+            domain: &str,
+            host: Host,
+            key: &str,
+            value: &SettingValue,
+        ) -> anyhow::Result<()> {
+            self.inner.write(domain, host, key, value)
+        }
+
+        fn delete(&self, domain: &str, host: Host, key: &str) -> anyhow::Result<()> {
+            self.inner.delete(domain, host, key)
+        }
+
+        fn modified(&self, domain: &str, host: Host) -> Option<SystemTime> {
+            self.inner.modified(domain, host)
+        }
+    }
+
+    #[test]
+    fn rereads_only_after_domains_change() {
+        let path = std::env::temp_dir().join(format!(
+            "myshenyatko-watcher-test-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let backend = Arc::new(CountingBackend {
+            inner: MemoryBackend::open(&path).unwrap(),
+            reads: AtomicUsize::new(0),
+        });
+        let rx = spawn(backend.clone(), all_settings(), Duration::from_millis(5));
+
+        rx.recv().unwrap();
+        let reads = backend.reads.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(backend.reads.load(Ordering::SeqCst), reads);
+
+        backend
+            .write(
+                "NSGlobalDomain",
+                Host::Any,
+                "KeyRepeat",
+                &SettingValue::Int(2),
+            )
+            .unwrap();
+        let refresh = rx.recv().unwrap();
+        assert_eq!(refresh.readings.values["key_repeat"], SettingValue::Int(2));
+        std::fs::remove_file(&path).unwrap();
+    }
+}